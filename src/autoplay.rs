@@ -0,0 +1,74 @@
+use std::{collections::HashSet, env};
+
+use serenity::model::prelude::GuildId;
+
+use crate::commands::ask::complete;
+use crate::history::recent;
+use crate::ytdl;
+
+/// How many recently played tracks are considered when picking what to play next, and are
+/// kept from being picked again.
+const SEED_TRACKS: usize = 5;
+
+/// Picks a track to keep the music going once the queue has run out, based on what the guild
+/// played recently. YouTube's related videos for the last track are tried first, then the
+/// chat model is asked for a recommendation if `OPENAI_API_KEY` is set.
+pub async fn pick_related(guild_id: GuildId) -> Option<String> {
+    let history = recent(guild_id).await;
+    let last = history.first()?;
+
+    let played: HashSet<String> = history
+        .iter()
+        .filter_map(|entry| ytdl::video_id(&entry.url))
+        .collect();
+
+    if let Some(video_id) = ytdl::video_id(&last.url) {
+        match ytdl::related(&video_id).await {
+            Ok(entries) => {
+                if let Some(entry) = entries.iter().find(|entry| !played.contains(&entry.id)) {
+                    return Some(entry.watch_url());
+                }
+            }
+            Err(why) => println!("Err finding related videos: {:?}", why),
+        }
+    }
+
+    if env::var("OPENAI_API_KEY").is_err() {
+        return None;
+    }
+
+    let titles = history
+        .iter()
+        .take(SEED_TRACKS)
+        .map(|entry| format!("- {}", entry.title))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let prompt = format!(
+        "These songs were played most recently, newest first:\n{}\n\
+         Recommend one different song that would fit well after them. \
+         Reply with only the artist and title, as \"Artist - Title\".",
+        titles
+    );
+
+    let recommendation = match complete(&prompt).await {
+        Ok(recommendation) => recommendation,
+        Err(why) => {
+            println!("Err asking for a recommendation: {:?}", why);
+            return None;
+        }
+    };
+
+    let query = recommendation.trim().trim_matches('"');
+
+    match ytdl::search(query, SEED_TRACKS).await {
+        Ok(results) => results
+            .into_iter()
+            .find(|entry| !played.contains(&entry.id))
+            .map(|entry| entry.watch_url()),
+        Err(why) => {
+            println!("Err searching for {}: {:?}", query, why);
+            None
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::HashSet, env, fs, io, path::PathBuf, time::SystemTime};
+
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+use serenity::prelude::Mutex;
+use songbird::input::Metadata;
+
+use crate::storage;
+use crate::ytdl;
+
+const DEFAULT_CACHE_MAX_MB: u64 = 1024;
+const AUDIO_EXTENSION: &str = "opus";
+const METADATA_EXTENSION: &str = "json";
+/// Marks files yt-dlp is still writing, as in `ID.part.opus`.
+const PARTIAL_SUFFIX: &str = ".part";
+/// The parts of yt-dlp's output that songbird reads metadata from, kept next to the audio.
+const METADATA_FIELDS: [&str; 10] = [
+    "title",
+    "track",
+    "artist",
+    "uploader",
+    "release_date",
+    "upload_date",
+    "duration",
+    "thumbnail",
+    "webpage_url",
+    "id",
+];
+
+lazy_static! {
+    /// Videos currently being downloaded into the cache.
+    static ref DOWNLOADING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Directory cached audio is kept in, set with `CACHE_DIR` and inside the data directory by
+/// default.
+pub fn cache_dir() -> PathBuf {
+    env::var("CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| storage::data_dir().join("cache"))
+}
+
+/// The most the cache may hold, in bytes, set in megabytes with `CACHE_MAX_MB`. Zero turns
+/// caching off.
+pub fn max_size() -> u64 {
+    env::var("CACHE_MAX_MB")
+        .ok()
+        .and_then(|megabytes| megabytes.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_CACHE_MAX_MB)
+        .saturating_mul(1024 * 1024)
+}
+
+/// Finds a cached video, marking it as recently used. Returns the audio file and the
+/// metadata saved with it.
+pub fn lookup(video_id: &str) -> Option<(PathBuf, Metadata)> {
+    if !is_valid_id(video_id) {
+        return None;
+    }
+
+    let audio_path = file_path(video_id, AUDIO_EXTENSION);
+    let info = fs::read(file_path(video_id, METADATA_EXTENSION)).ok()?;
+    let info: Value = serde_json::from_slice(&info).ok()?;
+
+    // Eviction goes by modification time, so touching the file keeps it around.
+    let audio = fs::File::options().append(true).open(&audio_path).ok()?;
+    let _ = audio.set_modified(SystemTime::now());
+
+    Some((audio_path, Metadata::from_ytdl_output(info)))
+}
+
+/// Downloads a video's audio into the cache in the background of whatever is streaming it,
+/// unless it's a live stream, then evicts the least recently used videos until the cache fits
+/// its size limit.
+pub async fn store(video_id: String, url: String, info: Value) {
+    if max_size() == 0 || !is_valid_id(&video_id) {
+        return;
+    }
+
+    // Live streams never finish downloading, so only videos with a length are cached.
+    let is_live = info.get("is_live").and_then(Value::as_bool) == Some(true)
+        || info.get("duration").map_or(true, Value::is_null);
+
+    if is_live {
+        return;
+    }
+
+    if !DOWNLOADING.lock().await.insert(video_id.clone()) {
+        return;
+    }
+
+    let info: Map<String, Value> = METADATA_FIELDS
+        .iter()
+        .filter_map(|field| Some((field.to_string(), info.get(*field)?.clone())))
+        .collect();
+
+    if let Err(why) = download(&video_id, &url, &Value::Object(info)).await {
+        println!("Err caching {}: {:?}", video_id, why);
+        remove_partial(&video_id);
+    }
+
+    DOWNLOADING.lock().await.remove(&video_id);
+
+    if let Err(why) = evict(max_size()) {
+        println!("Err evicting from the audio cache: {:?}", why);
+    }
+}
+
+/// How many videos are cached and how many bytes they take up, including unfinished
+/// downloads.
+pub fn stats() -> io::Result<(usize, u64)> {
+    let entries = entries()?;
+    let videos = entries.iter().filter(|entry| !entry.partial).count();
+    Ok((videos, entries.iter().map(|entry| entry.size).sum()))
+}
+
+/// Removes every cached video and unfinished download, returning how many videos were
+/// removed and the bytes freed.
+pub fn clear() -> io::Result<(usize, u64)> {
+    let stats = stats()?;
+    evict(0)?;
+    Ok(stats)
+}
+
+async fn download(
+    video_id: &str,
+    url: &str,
+    info: &Value,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    fs::create_dir_all(cache_dir())?;
+
+    // yt-dlp fills in the extension, so the partial download is named after the final one.
+    // The metadata is written last, since a video without it is never looked up.
+    let partial = file_path(&format!("{}{}", video_id, PARTIAL_SUFFIX), AUDIO_EXTENSION);
+    let template = cache_dir().join(format!("{}{}.%(ext)s", video_id, PARTIAL_SUFFIX));
+    ytdl::download_audio(url, &template.to_string_lossy(), AUDIO_EXTENSION).await?;
+
+    fs::rename(partial, file_path(video_id, AUDIO_EXTENSION))?;
+    fs::write(
+        file_path(video_id, METADATA_EXTENSION),
+        serde_json::to_vec(info)?,
+    )?;
+
+    Ok(())
+}
+
+/// Deletes whatever a failed download of `video_id` left behind, which may be in the format
+/// yt-dlp downloaded before converting it.
+fn remove_partial(video_id: &str) {
+    if let Ok(dir) = fs::read_dir(cache_dir()) {
+        for file in dir.flatten() {
+            if partial_id(&file.file_name().to_string_lossy()) == Some(video_id) {
+                let _ = fs::remove_file(file.path());
+            }
+        }
+    }
+}
+
+/// A cached video, or a download that never finished, and what it costs to keep.
+struct Entry {
+    /// The files that make it up, audio first.
+    paths: Vec<PathBuf>,
+    size: u64,
+    last_used: SystemTime,
+    partial: bool,
+}
+
+fn entries() -> io::Result<Vec<Entry>> {
+    let dir = match fs::read_dir(cache_dir()) {
+        Ok(dir) => dir,
+        Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(why) => return Err(why),
+    };
+
+    let mut entries = Vec::new();
+
+    for file in dir.flatten() {
+        let path = file.path();
+        let name = file.file_name().to_string_lossy().into_owned();
+
+        // Downloads that failed or were cut off by a restart still take up space.
+        if partial_id(&name).is_some() {
+            let partial = file.metadata()?;
+
+            entries.push(Entry {
+                paths: vec![path],
+                size: partial.len(),
+                last_used: partial.modified()?,
+                partial: true,
+            });
+
+            continue;
+        }
+
+        if path.extension().and_then(|extension| extension.to_str()) != Some(AUDIO_EXTENSION) {
+            continue;
+        }
+
+        let video_id = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(video_id) if is_valid_id(video_id) => video_id.to_string(),
+            _ => continue,
+        };
+
+        let audio = file.metadata()?;
+        let info_path = file_path(&video_id, METADATA_EXTENSION);
+        let info_size = fs::metadata(&info_path).map_or(0, |info| info.len());
+
+        entries.push(Entry {
+            paths: vec![path, info_path],
+            size: audio.len() + info_size,
+            last_used: audio.modified()?,
+            partial: false,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Removes the least recently used videos until the cache holds at most `limit` bytes.
+fn evict(limit: u64) -> io::Result<()> {
+    let mut entries = entries()?;
+    entries.sort_by_key(|entry| entry.last_used);
+
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+
+    for entry in entries {
+        if total <= limit {
+            break;
+        }
+
+        fs::remove_file(&entry.paths[0])?;
+        for path in &entry.paths[1..] {
+            let _ = fs::remove_file(path);
+        }
+        total -= entry.size;
+    }
+
+    Ok(())
+}
+
+/// The video a partial download such as `ID.part.webm` belongs to.
+fn partial_id(file_name: &str) -> Option<&str> {
+    let (video_id, _) = file_name.split_once(&format!("{}.", PARTIAL_SUFFIX))?;
+    Some(video_id).filter(|video_id| is_valid_id(video_id))
+}
+
+fn file_path(name: &str, extension: &str) -> PathBuf {
+    cache_dir().join(format!("{}.{}", name, extension))
+}
+
+/// Video IDs end up in file names, so only the characters YouTube uses are allowed.
+pub fn is_valid_id(video_id: &str) -> bool {
+    !video_id.is_empty()
+        && video_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_valid_id_allows_youtube_ids() {
+        assert!(is_valid_id("dQw4w9WgXcQ"));
+        assert!(is_valid_id("a-b_C1"));
+    }
+
+    #[test]
+    fn is_valid_id_rejects_anything_unsafe_in_a_file_name() {
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("../config"));
+        assert!(!is_valid_id("a/b"));
+        assert!(!is_valid_id("id.part"));
+        assert!(!is_valid_id("ïd"));
+    }
+
+    #[test]
+    fn partial_id_finds_the_video_of_a_partial_download() {
+        assert_eq!(partial_id("dQw4w9WgXcQ.part.opus"), Some("dQw4w9WgXcQ"));
+        assert_eq!(partial_id("dQw4w9WgXcQ.part.webm"), Some("dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn partial_id_ignores_finished_and_unrelated_files() {
+        assert_eq!(partial_id("dQw4w9WgXcQ.opus"), None);
+        assert_eq!(partial_id("dQw4w9WgXcQ.json"), None);
+        assert_eq!(partial_id(".part.opus"), None);
+        assert_eq!(partial_id("../x.part.opus"), None);
+    }
+}
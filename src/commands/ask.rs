@@ -6,13 +6,16 @@ use serenity::{
     framework::standard::{macros::command, Args, CommandResult},
     model::{channel::Message, prelude::ChannelId},
 };
+use songbird::create_player;
 
 use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE};
 use reqwest::{Client as RequestClient, StatusCode};
 
 use lazy_static::lazy_static;
 
+use crate::music::{guild_settings, volume_multiplier};
 use crate::utils::check_msg;
+use crate::voice::{author_voice_channel, ensure_voice};
 
 use tokio::sync::Mutex;
 
@@ -21,8 +24,6 @@ use tracing::error;
 #[command]
 #[only_in(guilds)]
 pub async fn ask(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    let api_key = env::var("OPENAI_API_KEY").expect("token");
-
     let prompt = args.rest().to_string();
     let junko_partial_prompt = "I want you to act like Junko Enoshima from Danganronpa. I want you to respond and answer like Junko Enoshima using the tone, manner and vocabulary Junko Enoshima would use. However I also need it to act as an AI assistant that is willing to answer anything about any topic. Do not write any explanations. Only answer like Junko Enoshima. You must know all of the knowledge of Junko Enoshima.".to_string();
     let full_prompt = [junko_partial_prompt, prompt].join("\n");
@@ -36,6 +37,35 @@ pub async fn ask(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     };
     channel_conversations.push(user_message);
 
+    let result = chat_completion(channel_conversations.clone()).await?;
+
+    check_msg(msg.channel_id.say(&ctx.http, &result).await);
+
+    speak(&ctx, &msg, result.to_string()).await?;
+
+    let ai_message = ChatMessage {
+        role: "assistant".to_string(),
+        content: result,
+    };
+    channel_conversations.push(ai_message);
+
+    Ok(())
+}
+
+/// Asks the chat model a one-off question, outside of any channel's conversation.
+pub async fn complete(prompt: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    chat_completion(vec![ChatMessage {
+        role: "user".to_string(),
+        content: prompt.to_string(),
+    }])
+    .await
+}
+
+async fn chat_completion(
+    messages: Vec<ChatMessage>,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let api_key = env::var("OPENAI_API_KEY").expect("token");
+
     let request_client = RequestClient::new();
 
     let mut headers = HeaderMap::new();
@@ -47,7 +77,7 @@ pub async fn ask(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 
     let request_body = ChatCompletionRequest {
         model: "gpt-4o".to_string(),
-        messages: channel_conversations.clone(),
+        messages,
     };
 
     let response = request_client
@@ -60,25 +90,13 @@ pub async fn ask(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     match response.status() {
         StatusCode::OK => {
             let response = response.json::<ChatCompletionResponse>().await?;
-            let result = &response.choices[0].message.content;
-
-            check_msg(msg.channel_id.say(&ctx.http, result).await);
-
-            speak(&ctx, &msg, result.to_string()).await?;
-
-            let ai_message = ChatMessage {
-                role: "assistant".to_string(),
-                content: result.to_string(),
-            };
-            channel_conversations.push(ai_message);
+            Ok(response.choices[0].message.content.clone())
         }
         _ => {
             error!("Failed to send HTTP request");
-            return Err("HTTP request failed".into());
+            Err("HTTP request failed".into())
         }
     }
-
-    Ok(())
 }
 
 async fn speak(
@@ -127,12 +145,21 @@ async fn speak(
                 .expect("Songbird Voice client placed in initialisation.")
                 .clone();
 
-            if let Some(handler_lock) = manager.get(guild_id) {
+            // Only join voice for someone who is in a channel; otherwise the text reply is enough.
+            let handler_lock = match author_voice_channel(ctx, msg) {
+                Some(_) => ensure_voice(ctx, msg).await,
+                None => manager.get(guild_id),
+            };
+
+            if let Some(handler_lock) = handler_lock {
                 let mut handler = handler_lock.lock().await;
 
                 let source = songbird::ffmpeg("./output.mp3").await.unwrap();
                 println!("Playing output.mp3 in the voice channel");
-                handler.play_source(source);
+                let (mut track, _) = create_player(source);
+                let tts_volume = guild_settings(guild_id).await.tts_volume;
+                track.set_volume(volume_multiplier(tts_volume));
+                handler.play(track);
             } else {
                 println!("No handler found for the guild");
             }
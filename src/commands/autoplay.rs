@@ -0,0 +1,46 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{get_autoplay, set_autoplay};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(radiomode)]
+pub async fn autoplay(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    // A bare `~autoplay` toggles it.
+    let enabled = match args.single::<String>() {
+        Ok(state) => match state.to_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, "Autoplay must be on or off")
+                        .await,
+                );
+
+                return Ok(());
+            }
+        },
+        Err(_) => !get_autoplay(guild_id).await,
+    };
+
+    set_autoplay(guild_id, enabled).await;
+
+    let reply = if enabled {
+        "Autoplay is on, related tracks will be played when the queue runs out"
+    } else {
+        "Autoplay is off"
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
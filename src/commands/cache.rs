@@ -0,0 +1,43 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::cache::{clear, max_size, stats};
+use crate::utils::check_msg;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+#[command]
+#[owners_only]
+pub async fn cache(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    let reply = match action.as_str() {
+        "stats" | "" => {
+            let (count, size) = stats()?;
+
+            format!(
+                "Audio cache: {} tracks, {:.1} MB of {:.0} MB",
+                count,
+                size as f64 / BYTES_PER_MB,
+                max_size() as f64 / BYTES_PER_MB
+            )
+        }
+        "clear" => {
+            let (count, size) = clear()?;
+
+            format!(
+                "Cleared {} tracks ({:.1} MB) from the audio cache",
+                count,
+                size as f64 / BYTES_PER_MB
+            )
+        }
+        _ => "Usage: `~cache stats` or `~cache clear`".to_string(),
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{is_dj, is_requester};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn clear(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        // DJs clear everything that's upcoming; everyone else only clears their own tracks.
+        let dj = is_dj(ctx, msg).await;
+        let handler = handler_lock.lock().await;
+        let queue = handler.queue();
+
+        let mut to_remove = HashSet::new();
+        for track in queue.current_queue().iter().skip(1) {
+            if dj || is_requester(track, msg.author.id).await {
+                to_remove.insert(track.uuid());
+            }
+        }
+
+        let removed = queue.modify_queue(|tracks| {
+            let mut removed = Vec::new();
+            let mut index = 1;
+
+            while index < tracks.len() {
+                if to_remove.contains(&tracks[index].uuid()) {
+                    removed.extend(tracks.remove(index));
+                } else {
+                    index += 1;
+                }
+            }
+
+            removed
+        });
+
+        for track in &removed {
+            let _ = track.stop();
+        }
+
+        let reply = match (removed.len(), dj) {
+            (0, _) => "Nothing to clear".to_string(),
+            (count, true) => format!("Cleared {} track(s) from the queue", count),
+            (count, false) => format!("Cleared {} of your track(s) from the queue", count),
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
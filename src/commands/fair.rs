@@ -0,0 +1,68 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::commands::play::apply_fair_order;
+use crate::music::{guild_settings, is_dj, update_guild_settings};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(fairqueue)]
+pub async fn fair(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(
+            msg.reply(ctx, "Only DJs can change how the queue is ordered")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    // A bare `~fair` toggles it.
+    let enabled = match args.single::<String>() {
+        Ok(state) => match state.to_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, "Fair queueing must be on or off")
+                        .await,
+                );
+
+                return Ok(());
+            }
+        },
+        Err(_) => !guild_settings(guild_id).await.fair_queue,
+    };
+
+    update_guild_settings(guild_id, |settings| settings.fair_queue = enabled).await;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    // Tracks already queued are shuffled into turns straight away.
+    if enabled {
+        if let Some(handler_lock) = manager.get(guild_id) {
+            apply_fair_order(&*handler_lock.lock().await).await;
+        }
+    }
+
+    let reply = if enabled {
+        "Fair queueing is on, requesters now take turns"
+    } else {
+        "Fair queueing is off, tracks play in the order they were added"
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{get_filter, is_dj, set_filter, AudioFilter};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn filter(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if args.is_empty() {
+        check_msg(
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("Audio filter is {}", get_filter(guild_id).await),
+                )
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can change the audio filter").await);
+
+        return Ok(());
+    }
+
+    let filter = match AudioFilter::parse(args.rest()) {
+        Some(filter) => filter,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "Filter must be one of: off, bassboost, nightcore, 8d, speed <0.5-2.0>",
+                    )
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    set_filter(guild_id, filter).await;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    // Sources read the filter whenever they start, so upcoming tracks need nothing more.
+    // Seeking restarts the current track's source, which applies the filter from here on.
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let current = handler_lock.lock().await.queue().current();
+
+        if let Some(current) = current {
+            if let Ok(state) = current.get_info().await {
+                let _ = current.seek_time(state.position);
+            }
+        }
+    }
+
+    check_msg(
+        msg.channel_id
+            .say(&ctx.http, format!("Audio filter set to {}", filter))
+            .await,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::history::{recent, HISTORY_LIMIT};
+use crate::utils::{check_msg, send_paginated};
+
+const DEFAULT_HISTORY_COUNT: usize = 10;
+const TRACKS_PER_PAGE: usize = 10;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(played)]
+pub async fn history(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let count = args
+        .single::<usize>()
+        .unwrap_or(DEFAULT_HISTORY_COUNT)
+        .clamp(1, HISTORY_LIMIT);
+
+    let entries = recent(guild_id).await;
+
+    if entries.is_empty() {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Nothing has been played yet")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let lines: Vec<String> = entries
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(index, entry)| {
+            let title = if entry.url.starts_with("http") {
+                format!("[{}]({})", entry.title, entry.url)
+            } else {
+                entry.title.clone()
+            };
+
+            format!(
+                "`{}.` {} | <@{}> <t:{}:R>",
+                index + 1,
+                title,
+                entry.requester,
+                entry.played_at
+            )
+        })
+        .collect();
+
+    let page_count = (lines.len() + TRACKS_PER_PAGE - 1) / TRACKS_PER_PAGE;
+
+    let pages = lines
+        .chunks(TRACKS_PER_PAGE)
+        .enumerate()
+        .map(|(page, chunk)| {
+            let mut embed = CreateEmbed::default();
+            embed
+                .title("Recently played")
+                .description(chunk.join("\n"))
+                .footer(|f| {
+                    f.text(format!(
+                        "Page {}/{} | Use ~replay <n> to queue one again",
+                        page + 1,
+                        page_count
+                    ))
+                });
+            embed
+        })
+        .collect();
+
+    send_paginated(ctx, msg.channel_id, msg.author.id, pages).await?;
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ use serenity::{
 };
 
 use crate::utils::check_msg;
+use crate::voice::{author_voice_channel, join_voice};
 
 #[command]
 #[only_in(guilds)]
@@ -12,12 +13,7 @@ pub async fn join(ctx: &Context, msg: &Message) -> CommandResult {
     let guild = msg.guild(&ctx.cache).unwrap();
     let guild_id = guild.id;
 
-    let channel_id = guild
-        .voice_states
-        .get(&msg.author.id)
-        .and_then(|voice_state| voice_state.channel_id);
-
-    let connect_to = match channel_id {
+    let connect_to = match author_voice_channel(ctx, msg) {
         Some(channel) => channel,
         None => {
             check_msg(msg.reply(ctx, "Not in a voice channel").await);
@@ -25,12 +21,13 @@ pub async fn join(ctx: &Context, msg: &Message) -> CommandResult {
         }
     };
 
-    let manager = songbird::get(ctx)
-        .await
-        .expect("Songbird Voice client placed in at initialisation")
-        .clone();
-
-    let _handler = manager.join(guild_id, connect_to).await;
+    if let Err(why) = join_voice(ctx, guild_id, connect_to, msg.channel_id).await {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, format!("Failed to join: {:?}", why))
+                .await,
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,88 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::is_dj;
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn jump(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let position = match args.single::<usize>() {
+        Ok(position) if position > 0 => position,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Must provide the position of a queued track")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can jump through the queue").await);
+
+        return Ok(());
+    }
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let handler = handler_lock.lock().await;
+        let queue = handler.queue();
+
+        // Drop everything between the playing track and the target, then skip the playing
+        // track so the target starts next.
+        let skipped = queue.modify_queue(|tracks| {
+            if position >= tracks.len() {
+                return None;
+            }
+
+            Some(tracks.drain(1..position).collect::<Vec<_>>())
+        });
+
+        match skipped {
+            Some(skipped) => {
+                for track in &skipped {
+                    let _ = track.stop();
+                }
+
+                let _ = queue.skip();
+
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, format!("Jumped to track {}", position))
+                        .await,
+                );
+            }
+            None => {
+                check_msg(
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            format!("There is no track at position {}", position),
+                        )
+                        .await,
+                );
+            }
+        }
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{guild_settings, is_dj, update_guild_settings};
+use crate::utils::check_msg;
+
+const USAGE: &str = "Usage: `~limit tracks <n|off>` or `~limit length <minutes|off>`";
+/// The highest either limit can be set to. Anything above it is as good as no limit.
+const MAX_VALUE: u64 = 10_000;
+
+#[command]
+#[only_in(guilds)]
+pub async fn limit(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if args.is_empty() {
+        let settings = guild_settings(guild_id).await;
+        let tracks = settings
+            .max_user_tracks
+            .map_or("no limit".to_string(), |max| format!("{} tracks", max));
+        let length = settings
+            .max_user_minutes
+            .map_or("no limit".to_string(), |max| format!("{} minutes", max));
+
+        check_msg(
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("Per-member queue limits: {}, {}", tracks, length),
+                )
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can change queue limits").await);
+
+        return Ok(());
+    }
+
+    let kind = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    // `off` removes a limit, anything else has to be a positive number up to `MAX_VALUE`.
+    let value = match args.single::<String>() {
+        Ok(value) if value.eq_ignore_ascii_case("off") => None,
+        Ok(value) => match value.parse::<u64>() {
+            Ok(value) if value > MAX_VALUE => {
+                check_msg(
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            format!("Limits can be at most {}, or `off`", MAX_VALUE),
+                        )
+                        .await,
+                );
+
+                return Ok(());
+            }
+            Ok(value) if value > 0 => Some(value),
+            _ => {
+                check_msg(msg.channel_id.say(&ctx.http, USAGE).await);
+
+                return Ok(());
+            }
+        },
+        Err(_) => {
+            check_msg(msg.channel_id.say(&ctx.http, USAGE).await);
+
+            return Ok(());
+        }
+    };
+
+    let reply = match (kind.as_str(), value) {
+        ("tracks", value) => {
+            update_guild_settings(guild_id, |settings| {
+                settings.max_user_tracks = value.map(|value| value as usize)
+            })
+            .await;
+
+            value.map_or("Removed the track limit".to_string(), |value| {
+                format!("Members can now queue up to {} tracks at once", value)
+            })
+        }
+        ("length" | "minutes", value) => {
+            update_guild_settings(guild_id, |settings| settings.max_user_minutes = value).await;
+
+            value.map_or("Removed the length limit".to_string(), |value| {
+                format!(
+                    "Members can now queue up to {} minutes of music at once",
+                    value
+                )
+            })
+        }
+        _ => USAGE.to_string(),
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
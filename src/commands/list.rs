@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use songbird::tracks::TrackHandle;
+
+use crate::music::{format_duration, format_length, time_until, track_data};
+use crate::utils::{check_msg, send_paginated};
+
+const TRACKS_PER_PAGE: usize = 10;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(queue)]
+pub async fn list(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Not in a voice channel to play in")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let tracks = handler_lock.lock().await.queue().current_queue();
+
+    let (current, upcoming) = match tracks.split_first() {
+        Some(split) => split,
+        None => {
+            check_msg(msg.channel_id.say(&ctx.http, "The queue is empty.").await);
+
+            return Ok(());
+        }
+    };
+
+    let now_playing = match current.get_info().await {
+        Ok(state) => format!(
+            "{} `{}`",
+            describe_track(current).await,
+            progress(current, state.position)
+        ),
+        Err(_) => describe_track(current).await,
+    };
+
+    let mut lines = Vec::with_capacity(upcoming.len());
+    for (index, handle) in upcoming.iter().enumerate() {
+        lines.push(format!("`{}.` {}", index + 1, describe_track(handle).await));
+    }
+
+    let page_count = ((lines.len() + TRACKS_PER_PAGE - 1) / TRACKS_PER_PAGE).max(1);
+
+    let remaining = match time_until(guild_id, &tracks, tracks.len()).await {
+        Some(remaining) => format!("{} remaining", format_length(remaining)),
+        None => "plays until the live stream is skipped".to_string(),
+    };
+
+    let pages = (0..page_count)
+        .map(|page| {
+            let start = page * TRACKS_PER_PAGE;
+            let end = (start + TRACKS_PER_PAGE).min(lines.len());
+
+            let up_next = if lines.is_empty() {
+                "Nothing else queued.".to_string()
+            } else {
+                lines[start..end].join("\n")
+            };
+
+            let mut embed = CreateEmbed::default();
+            embed
+                .title("Queue")
+                .field("Now playing", &now_playing, false)
+                .field("Up next", up_next, false)
+                .footer(|f| {
+                    f.text(format!(
+                        "Page {}/{} | {} track(s) queued | {}",
+                        page + 1,
+                        page_count,
+                        upcoming.len(),
+                        remaining
+                    ))
+                });
+            embed
+        })
+        .collect();
+
+    send_paginated(ctx, msg.channel_id, msg.author.id, pages).await?;
+
+    Ok(())
+}
+
+/// Formats a track as a linked title with its duration and requester.
+async fn describe_track(handle: &TrackHandle) -> String {
+    let metadata = handle.metadata();
+    let title = metadata
+        .title
+        .clone()
+        .unwrap_or_else(|| "Unknown title".to_string());
+    let duration = metadata
+        .duration
+        .map(format_duration)
+        .unwrap_or_else(|| "live".to_string());
+
+    match track_data(handle).await {
+        Some(data) => match data.link() {
+            Some(link) => format!(
+                "[{}]({}) `{}` | <@{}>",
+                title, link, duration, data.requester
+            ),
+            None => format!("{} `{}` | <@{}>", title, duration, data.requester),
+        },
+        None => format!("{} `{}`", title, duration),
+    }
+}
+
+fn progress(handle: &TrackHandle, position: Duration) -> String {
+    match handle.metadata().duration {
+        Some(duration) => format!(
+            "{} / {}",
+            format_duration(position),
+            format_duration(duration)
+        ),
+        None => format_duration(position),
+    }
+}
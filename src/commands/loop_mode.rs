@@ -0,0 +1,62 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{get_loop_mode, set_loop_mode, LoopMode};
+use crate::utils::check_msg;
+
+#[command("loop")]
+#[only_in(guilds)]
+#[aliases(repeat)]
+pub async fn loop_mode(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let mode = match args.single::<String>() {
+        Ok(mode) => match mode.to_lowercase().as_str() {
+            "off" => LoopMode::Off,
+            "track" | "song" | "one" => LoopMode::Track,
+            "queue" | "all" => LoopMode::Queue,
+            _ => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, "Loop mode must be one of: off, track, queue")
+                        .await,
+                );
+
+                return Ok(());
+            }
+        },
+        Err(_) => get_loop_mode(guild_id).await.next(),
+    };
+
+    set_loop_mode(guild_id, mode).await;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    // Every queued track carries its own loop flag, so the whole queue is updated to keep
+    // upcoming tracks consistent with the new mode.
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let tracks = handler_lock.lock().await.queue().current_queue();
+
+        for track in tracks {
+            let _ = match mode {
+                LoopMode::Track => track.enable_loop(),
+                _ => track.disable_loop(),
+            };
+        }
+    }
+
+    check_msg(
+        msg.channel_id
+            .say(&ctx.http, format!("Loop mode set to {}", mode))
+            .await,
+    );
+
+    Ok(())
+}
@@ -1,8 +1,34 @@
 pub mod ask;
+pub mod autoplay;
+pub mod cache;
+pub mod clear;
+pub mod fair;
+pub mod filter;
+pub mod history;
 pub mod join;
+pub mod jump;
 pub mod leave;
+pub mod limit;
+pub mod list;
+pub mod loop_mode;
+pub mod move_track;
 pub mod mvp;
+pub mod np;
+pub mod pause;
 pub mod ping;
 pub mod play;
+pub mod playlist;
+pub mod playnext;
+pub mod previous;
+pub mod radio;
+pub mod remove;
+pub mod replay;
+pub mod rescan;
+pub mod resume;
+pub mod retry;
+pub mod seek;
+pub mod shuffle;
 pub mod skip;
+pub mod stop;
 pub mod tts;
+pub mod volume;
@@ -0,0 +1,75 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::is_dj;
+use crate::utils::check_msg;
+
+#[command("move")]
+#[only_in(guilds)]
+pub async fn move_track(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (from, to) = match (args.single::<usize>(), args.single::<usize>()) {
+        (Ok(from), Ok(to)) if from > 0 && to > 0 => (from, to),
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Usage: ~move <from> <to>")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can reorder the queue").await);
+
+        return Ok(());
+    }
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let handler = handler_lock.lock().await;
+
+        // Position 0 is the playing track, which stays where it is.
+        let moved = handler.queue().modify_queue(|tracks| {
+            if from >= tracks.len() || to >= tracks.len() {
+                return None;
+            }
+
+            let track = tracks.remove(from)?;
+            let title = track.metadata().title.clone();
+            tracks.insert(to, track);
+
+            Some(title)
+        });
+
+        let reply = match moved {
+            Some(title) => format!(
+                "Moved {} to position {}",
+                title.unwrap_or_else(|| "Unknown title".to_string()),
+                to
+            ),
+            None => "Both positions must be in the queue".to_string(),
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
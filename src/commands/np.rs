@@ -0,0 +1,102 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{format_duration, progress_bar, track_data, TrackData};
+use crate::radio::stream_title;
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(nowplaying)]
+pub async fn np(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Not in a voice channel to play in")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let current = handler_lock.lock().await.queue().current();
+
+    let current = match current {
+        Some(current) => current,
+        None => {
+            check_msg(msg.channel_id.say(&ctx.http, "Nothing is playing").await);
+
+            return Ok(());
+        }
+    };
+
+    let position = match current.get_info().await {
+        Ok(state) => state.position,
+        Err(_) => {
+            check_msg(msg.channel_id.say(&ctx.http, "Nothing is playing").await);
+
+            return Ok(());
+        }
+    };
+
+    let metadata = current.metadata().clone();
+    let data = track_data(&current).await;
+
+    let progress = match metadata.duration {
+        Some(duration) => format!(
+            "{} `{} / {}`",
+            progress_bar(position, duration),
+            format_duration(position),
+            format_duration(duration)
+        ),
+        None => format!("`{}` (live)", format_duration(position)),
+    };
+
+    // Radio streams report the song they're on separately from the station name.
+    let progress = match stream_title(&current).await {
+        Some(song) => format!("🎵 {}\n{}", song, progress),
+        None => progress,
+    };
+
+    msg.channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Now playing").description(format!(
+                    "**{}**\n{}",
+                    metadata.title.as_deref().unwrap_or("Unknown title"),
+                    progress
+                ));
+
+                if let Some(link) = data.as_ref().and_then(TrackData::link) {
+                    e.url(link);
+                }
+
+                if let Some(thumbnail) = &metadata.thumbnail {
+                    e.thumbnail(thumbnail);
+                }
+
+                if let Some(data) = &data {
+                    e.field("Requested by", format!("<@{}>", data.requester), true);
+                }
+
+                e
+            })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -1,111 +1,1044 @@
-use std::sync::Arc;
+use std::{env, fmt, sync::Arc, time::Duration};
+
+use futures::{stream, StreamExt};
 
 use serenity::{
     async_trait,
     client::Context,
     framework::standard::{macros::command, Args, CommandResult},
-    http::Http,
-    model::{channel::Message, prelude::ChannelId},
+    model::{
+        channel::{Message, ReactionType},
+        prelude::{ChannelId, GuildId, UserId},
+    },
+    prelude::{Mutex, TypeMapKey},
 };
 
 use songbird::{
-    input::restartable::Restartable, Event, EventContext, EventHandler as VoiceEventHandler,
-    TrackEvent,
+    create_player,
+    input::Input,
+    tracks::{PlayMode, Queued, TrackHandle},
+    Call, Event, EventContext, EventHandler as VoiceEventHandler, TrackEvent,
 };
 
+use crate::autoplay;
+use crate::history;
+use crate::library;
+use crate::music::{
+    self, fair_slot, format_duration, format_length, get_autoplay, get_filter, get_loop_mode,
+    guild_settings, has_dj_role, round_robin_order, time_until, track_embed, volume_multiplier,
+    GuildSettings, LoopMode, TrackData,
+};
+use crate::radio;
+use crate::source::{self, LOCAL_PREFIX, RADIO_PREFIX};
 use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+use crate::ytdl;
+
+const SEARCH_RESULTS: usize = 5;
+const SELECTION_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_PLAYLIST_LIMIT: usize = 50;
+const PLAYLIST_CONCURRENCY: usize = 4;
+/// How far short of its length a track can end before it counts as having failed.
+const FAILURE_MARGIN: Duration = Duration::from_secs(5);
 
 #[command]
 #[only_in(guilds)]
-pub async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    let url = match args.single::<String>() {
-        Ok(url) => url,
-        Err(_) => {
+pub async fn play(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let query = args.rest().trim();
+    let attachments: Vec<String> = msg
+        .attachments
+        .iter()
+        .filter(|attachment| library::is_audio_file(&attachment.filename))
+        .map(|attachment| attachment.url.clone())
+        .collect();
+
+    if query.is_empty() && attachments.is_empty() {
+        check_msg(
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "Must provide a URL, something to search for or an audio file",
+                )
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    if !attachments.is_empty() {
+        for url in attachments {
+            enqueue_url(ctx, msg, &handler_lock, url, Placement::Back).await?;
+        }
+
+        return Ok(());
+    }
+
+    // `~play <link> all` queues the whole playlist or mix a video link was opened from.
+    let (query, whole_list) = match query.rsplit_once(char::is_whitespace) {
+        Some((link, "all")) if link.starts_with("http") => (link.trim(), true),
+        _ => (query, false),
+    };
+
+    let url = match resolve_query(ctx, msg, query).await? {
+        Some(url) => url,
+        None => return Ok(()),
+    };
+
+    if whole_list || ytdl::is_playlist_url(&url) {
+        return enqueue_playlist(ctx, msg, &handler_lock, &url).await;
+    }
+
+    enqueue_url(ctx, msg, &handler_lock, url, Placement::Back).await
+}
+
+/// Turns what was passed to a play command into a track URL: `local:` queries are matched
+/// against the music library, links are used as they are and anything else is searched for.
+/// Replies and returns `None` when nothing was picked.
+pub async fn resolve_query(
+    ctx: &Context,
+    msg: &Message,
+    query: &str,
+) -> CommandResult<Option<String>> {
+    if let Some(name) = query.strip_prefix(LOCAL_PREFIX) {
+        let name = name.trim();
+
+        if name.is_empty() {
             check_msg(
                 msg.channel_id
-                    .say(&ctx.http, "Must provide a URL to a video or audio")
+                    .say(&ctx.http, "Usage: ~play local:<name>")
                     .await,
             );
 
+            return Ok(None);
+        }
+
+        return match library::search(name).await.first() {
+            Some(file) => Ok(Some(format!("{}{}", LOCAL_PREFIX, file.location))),
+            None => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, format!("No local track matches \"{}\"", name))
+                        .await,
+                );
+
+                Ok(None)
+            }
+        };
+    }
+
+    if query.starts_with("http") {
+        Ok(Some(query.to_string()))
+    } else {
+        choose_search_result(ctx, msg, query).await
+    }
+}
+
+/// Where a newly requested track goes in the queue.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Behind everything already queued.
+    Back,
+    /// Straight after the track that's playing.
+    Next,
+}
+
+/// Creates a source for a single track, enqueues it and replies with where it landed.
+pub async fn enqueue_url(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<Mutex<Call>>,
+    url: String,
+    placement: Placement,
+) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    // Here, we use lazy restartable sources to make sure that we don't pay
+    // for decoding, playback on tracks which aren't actually live yet.
+    let source = match source::open(guild_id, url.clone()).await {
+        Ok(source) => source,
+        Err(why) => {
+            println!("Err starting source: {:?}", why);
+
+            check_msg(msg.channel_id.say(&ctx.http, "Error sourcing ffmpeg").await);
+
             return Ok(());
         }
     };
 
-    if !url.starts_with("http") {
+    let source: Input = source.into();
+    let exempt = has_dj_role(ctx, msg).await;
+    let settings = guild_settings(guild_id).await;
+
+    let (song, queue) = {
+        let mut handler = handler_lock.lock().await;
+
+        if !exempt {
+            let length = source.metadata.duration;
+
+            if let Some(reason) = limit_reason(&handler, &settings, msg.author.id, length).await {
+                drop(handler);
+                check_msg(msg.reply(ctx, reason).await);
+
+                return Ok(());
+            }
+        }
+
+        let song = enqueue(ctx, guild_id, &mut handler, source, requested_by(msg, url)).await;
+
+        if placement == Placement::Next {
+            play_next(&handler, &song).await;
+        }
+
+        (song, handler.queue().current_queue())
+    };
+
+    // Fair queueing may have placed the track ahead of others.
+    let position = queue
+        .iter()
+        .position(|track| track.uuid() == song.uuid())
+        .unwrap_or_default();
+
+    // A track that starts straight away is announced as now playing instead.
+    if position == 0 {
+        announce_now_playing(ctx, &song).await;
+    } else {
+        let data = music::track_data(&song).await;
+        let heading = match placement {
+            Placement::Back => "Added to queue",
+            Placement::Next => "Playing next",
+        };
+        let mut embed = track_embed(heading, &song, data.as_ref());
+        embed.field("Position", position, true);
+        embed.field(
+            "Plays in",
+            time_until(guild_id, &queue, position)
+                .await
+                .map_or("after a live stream".to_string(), |eta| {
+                    format!("~{}", format_length(eta))
+                }),
+            true,
+        );
+
         check_msg(
             msg.channel_id
-                .say(&ctx.http, "Must provide a valid URL")
+                .send_message(&ctx.http, |m| m.set_embed(embed))
                 .await,
         );
+    }
 
-        return Ok(());
+    Ok(())
+}
+
+/// Adds a source to the back of the queue, tagging it with its requester and registering
+/// the end-of-track notifier for the channel it was requested from.
+pub async fn enqueue(
+    ctx: &Context,
+    guild_id: GuildId,
+    handler: &mut Call,
+    source: Input,
+    data: TrackData,
+) -> TrackHandle {
+    let settings = guild_settings(guild_id).await;
+
+    let (mut track, song) = create_player(source);
+    track.set_volume(volume_multiplier(settings.volume));
+    handler.enqueue(track);
+
+    let chan_id = data.channel;
+
+    if let Some(stream_url) = data.url.strip_prefix(RADIO_PREFIX) {
+        tokio::spawn(radio::watch(
+            ctx.clone(),
+            song.clone(),
+            stream_url.to_string(),
+            chan_id,
+        ));
     }
 
-    let guild = msg.guild(&ctx.cache).unwrap();
-    let guild_id = guild.id;
+    song.typemap().write().await.insert::<TrackData>(data);
 
-    let manager = songbird::get(ctx)
-        .await
-        .expect("Songbird Voice client placed in at initialisation")
-        .clone();
+    if settings.fair_queue {
+        place_fairly(handler, &song).await;
+    }
 
-    if let Some(handler_lock) = manager.get(guild_id) {
-        let mut handler = handler_lock.lock().await;
+    if get_loop_mode(guild_id).await == LoopMode::Track {
+        let _ = song.enable_loop();
+    }
 
-        // Here, we use lazy restartable sources to make sure that we don't pay
-        // for decoding, playback on tracks which aren't actually live yet.
-        let source = match Restartable::ytdl(url, true).await {
-            Ok(source) => source,
-            Err(why) => {
-                println!("Err starting source: {:?}", why);
+    let _ = song.add_event(
+        Event::Track(TrackEvent::End),
+        SongEndNotifier {
+            ctx: ctx.clone(),
+            guild_id,
+            chan_id,
+        },
+    );
 
-                check_msg(msg.channel_id.say(&ctx.http, "Error sourcing ffmpeg").await);
+    song
+}
 
-                return Ok(());
+/// Reorders the upcoming tracks so requesters take turns, each keeping their own tracks in
+/// the order they queued them. Tracks put first with `play_next` stay where they are.
+pub async fn apply_fair_order(handler: &Call) {
+    let snapshot = handler.queue().current_queue();
+    let (current, upcoming) = match snapshot.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let current = music::track_data(current).await.map(|data| data.requester);
+    let (requesters, pinned) = upcoming_turns(upcoming).await;
+    let order = round_robin_order(Some(current), &requesters[pinned..]);
+
+    handler.queue().modify_queue(|queue| {
+        // Leave the queue alone if it moved on while the requesters were being read.
+        let unchanged = queue.len() == snapshot.len()
+            && queue
+                .iter()
+                .zip(&snapshot)
+                .all(|(queued, handle)| queued.uuid() == handle.uuid());
+
+        if !unchanged {
+            return;
+        }
+
+        let mut tracks: Vec<Option<Queued>> = queue.drain(1 + pinned..).map(Some).collect();
+        for index in order {
+            queue.extend(tracks[index].take());
+        }
+    });
+}
+
+/// Moves a track just added to the back of the queue to its requester's next turn, leaving
+/// the rest of the queue as it is.
+async fn place_fairly(handler: &Call, song: &TrackHandle) {
+    let snapshot = handler.queue().current_queue();
+
+    // The track is last, and there's nothing to do if it's the only one waiting.
+    let upcoming = match snapshot.split_last() {
+        Some((last, rest)) if last.uuid() == song.uuid() && rest.len() > 1 => &rest[1..],
+        _ => return,
+    };
+
+    let current = music::track_data(&snapshot[0])
+        .await
+        .map(|data| data.requester);
+    let requester = music::track_data(song).await.map(|data| data.requester);
+    let (requesters, pinned) = upcoming_turns(upcoming).await;
+    let slot = fair_slot(Some(current), &requesters, requester).max(pinned);
+
+    handler.queue().modify_queue(|queue| {
+        let unchanged = queue.len() == snapshot.len()
+            && queue.back().map(|last| last.uuid()) == Some(song.uuid());
+
+        if unchanged {
+            if let Some(track) = queue.pop_back() {
+                queue.insert(1 + slot, track);
             }
+        }
+    });
+}
+
+/// Who requested each of the `upcoming` tracks, and how many of them at the front were put
+/// there with `play_next`.
+async fn upcoming_turns(upcoming: &[TrackHandle]) -> (Vec<Option<UserId>>, usize) {
+    let mut requesters = Vec::with_capacity(upcoming.len());
+    let mut pinned = 0;
+
+    for handle in upcoming {
+        let typemap = handle.typemap().read().await;
+
+        if typemap.contains_key::<PlayNext>() && pinned == requesters.len() {
+            pinned += 1;
+        }
+
+        requesters.push(typemap.get::<TrackData>().map(|data| data.requester));
+    }
+
+    (requesters, pinned)
+}
+
+/// Marks tracks put straight after the playing one, which fair queueing keeps in front.
+struct PlayNext;
+
+impl TypeMapKey for PlayNext {
+    type Value = ();
+}
+
+/// Moves a queued track to straight after the one that's playing, where fair queueing won't
+/// move it from. Returns whether it was found in the upcoming tracks.
+pub async fn play_next(handler: &Call, song: &TrackHandle) -> bool {
+    song.typemap().write().await.insert::<PlayNext>(());
+
+    handler.queue().modify_queue(|queue| {
+        let index = match queue.iter().position(|queued| queued.uuid() == song.uuid()) {
+            Some(index) if index > 0 => index,
+            _ => return false,
         };
 
-        let song = handler.enqueue_source(source.into());
-        let send_http = ctx.http.clone();
-        let chan_id = msg.channel_id;
+        if let Some(track) = queue.remove(index) {
+            queue.insert(1, track);
+        }
 
-        let _ = song.add_event(
-            Event::Track(TrackEvent::End),
-            SongEndNotifier {
-                chan_id,
-                http: send_http,
-            },
-        );
+        true
+    })
+}
+
+/// Starts a queued track straight away. The track that was playing is paused and goes next,
+/// so it carries on from where it was once the new one ends. Returns whether the track was
+/// found in the upcoming tracks.
+pub async fn play_now(handler: &Call, song: &TrackHandle) -> bool {
+    let displaced = handler.queue().modify_queue(|queue| {
+        let index = queue
+            .iter()
+            .position(|queued| queued.uuid() == song.uuid())
+            .filter(|index| *index > 0)?;
+        let track = queue.remove(index)?;
+
+        let displaced = queue.front().map(|playing| playing.handle());
+        if let Some(playing) = &displaced {
+            let _ = playing.pause();
+        }
+
+        let _ = track.play();
+        queue.push_front(track);
+
+        Some(displaced)
+    });
+
+    let displaced = match displaced {
+        Some(displaced) => displaced,
+        None => return false,
+    };
+
+    // Fair queueing leaves the paused track in front, like one put there with `play_next`.
+    if let Some(displaced) = displaced {
+        displaced.typemap().write().await.insert::<PlayNext>(());
+    }
+
+    true
+}
+
+/// Why `user` can't queue another track of `length`, if they're over one of the guild's
+/// per-member limits.
+pub async fn limit_reason(
+    handler: &Call,
+    settings: &GuildSettings,
+    user: UserId,
+    length: Option<Duration>,
+) -> Option<String> {
+    let usage = queued_by(handler, user).await;
+    over_limit(settings, &usage, length)
+}
+
+/// How much a member already has in the queue.
+struct Usage {
+    tracks: usize,
+    length: Duration,
+}
+
+async fn queued_by(handler: &Call, user: UserId) -> Usage {
+    let mut usage = Usage {
+        tracks: 0,
+        length: Duration::ZERO,
+    };
 
+    for handle in handler.queue().current_queue() {
+        if music::is_requester(&handle, user).await {
+            usage.tracks += 1;
+            usage.length += handle.metadata().duration.unwrap_or_default();
+        }
+    }
+
+    usage
+}
+
+/// Checks one more track of `length` against the guild's per-member limits, returning why it
+/// can't be queued.
+fn over_limit(settings: &GuildSettings, usage: &Usage, length: Option<Duration>) -> Option<String> {
+    if let Some(max_tracks) = settings.max_user_tracks {
+        if usage.tracks >= max_tracks {
+            return Some(format!(
+                "You can only have {} tracks queued at once",
+                max_tracks
+            ));
+        }
+    }
+
+    if let Some(max_minutes) = settings.max_user_minutes {
+        if usage.length + length.unwrap_or_default()
+            > Duration::from_secs(max_minutes.saturating_mul(60))
+        {
+            return Some(format!(
+                "You can only have {} minutes of music queued at once",
+                max_minutes
+            ));
+        }
+    }
+
+    None
+}
+
+/// Expands a playlist into one lazy source per entry, up to the configured limit, and
+/// enqueues them in order.
+async fn enqueue_playlist(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<Mutex<Call>>,
+    url: &str,
+) -> CommandResult {
+    let mut entries = match ytdl::playlist(url).await {
+        Ok(entries) => entries,
+        Err(why) => {
+            println!("Err reading playlist: {:?}", why);
+
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Error reading playlist")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let limit = playlist_limit();
+    let skipped = entries.len().saturating_sub(limit);
+    entries.truncate(limit);
+
+    if entries.is_empty() {
         check_msg(
             msg.channel_id
-                .say(
-                    &ctx.http,
-                    format!("Added song to queue: position {}", handler.queue().len()),
-                )
+                .say(&ctx.http, "That playlist has no tracks to play")
                 .await,
         );
-    } else {
+
+        return Ok(());
+    }
+
+    let urls = entries.iter().map(|entry| entry.watch_url()).collect();
+    let enqueued = enqueue_urls(ctx, msg, handler_lock, urls).await;
+
+    let mut reply = format!("Playlist: {}", enqueued);
+    if skipped > 0 {
+        reply.push_str(&format!(
+            ", skipped {} past the limit of {}",
+            skipped, limit
+        ));
+    }
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    announce_current(ctx, handler_lock).await;
+
+    Ok(())
+}
+
+/// The outcome of enqueueing a batch of tracks.
+pub struct Enqueued {
+    pub added: usize,
+    /// Combined length of the tracks that were added.
+    pub total: Duration,
+    /// Tracks left out because they would have gone over the requester's limits.
+    pub refused: usize,
+}
+
+impl fmt::Display for Enqueued {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "added {} tracks ({})",
+            self.added,
+            format_length(self.total)
+        )?;
+
+        if self.refused > 0 {
+            write!(f, ", {} more would go over your queue limit", self.refused)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates sources for several tracks at once and enqueues them in order, skipping any that
+/// can't be started or that would go over the requester's limits.
+pub async fn enqueue_urls(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<Mutex<Call>>,
+    urls: Vec<String>,
+) -> Enqueued {
+    let guild_id = msg.guild_id.unwrap();
+
+    let sources = stream::iter(urls)
+        .map(|url| async move {
+            let source = source::open(guild_id, url.clone()).await;
+            (url, source)
+        })
+        .buffered(PLAYLIST_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let exempt = has_dj_role(ctx, msg).await;
+    let settings = guild_settings(guild_id).await;
+
+    let mut handler = handler_lock.lock().await;
+    let mut usage = queued_by(&handler, msg.author.id).await;
+    let mut enqueued = Enqueued {
+        added: 0,
+        total: Duration::ZERO,
+        refused: 0,
+    };
+
+    for (url, source) in sources {
+        let source: Input = match source {
+            Ok(source) => source.into(),
+            Err(why) => {
+                println!("Err starting source for {}: {:?}", url, why);
+                continue;
+            }
+        };
+
+        let length = source.metadata.duration;
+        if !exempt && over_limit(&settings, &usage, length).is_some() {
+            enqueued.refused += 1;
+            continue;
+        }
+
+        enqueue(ctx, guild_id, &mut handler, source, requested_by(msg, url)).await;
+
+        usage.tracks += 1;
+        usage.length += length.unwrap_or_default();
+        enqueued.added += 1;
+        enqueued.total += length.unwrap_or_default();
+    }
+
+    enqueued
+}
+
+/// Announces the track at the head of the queue if that hasn't been done yet, for when a
+/// batch of tracks may have started playing.
+pub async fn announce_current(ctx: &Context, handler_lock: &Arc<Mutex<Call>>) {
+    let current = handler_lock.lock().await.queue().current();
+
+    if let Some(current) = current {
+        announce_now_playing(ctx, &current).await;
+    }
+}
+
+fn requested_by(msg: &Message, url: String) -> TrackData {
+    TrackData {
+        requester: msg.author.id,
+        url,
+        channel: msg.channel_id,
+    }
+}
+
+fn playlist_limit() -> usize {
+    env::var("PLAYLIST_LIMIT")
+        .ok()
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(DEFAULT_PLAYLIST_LIMIT)
+}
+
+/// Searches for `query`, shows the top results and waits for the requester to pick one,
+/// either by replying with its number or reacting with the matching keycap.
+async fn choose_search_result(
+    ctx: &Context,
+    msg: &Message,
+    query: &str,
+) -> CommandResult<Option<String>> {
+    let results = match ytdl::search(query, SEARCH_RESULTS).await {
+        Ok(results) => results,
+        Err(why) => {
+            println!("Err searching: {:?}", why);
+
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Error searching yt-dlp")
+                    .await,
+            );
+
+            return Ok(None);
+        }
+    };
+
+    if results.is_empty() {
         check_msg(
             msg.channel_id
-                .say(&ctx.http, "Not in a voice channel to play in")
+                .say(&ctx.http, format!("No results found for \"{}\"", query))
                 .await,
         );
+
+        return Ok(None);
     }
 
-    Ok(())
+    let description = results
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            format!(
+                "`{}.` {} `{}`",
+                index + 1,
+                entry.title.as_deref().unwrap_or("Unknown title"),
+                entry
+                    .duration()
+                    .map(format_duration)
+                    .unwrap_or_else(|| "live".to_string())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let results_msg = msg
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Results for \"{}\"", query))
+                    .description(description)
+                    .footer(|f| f.text("Reply with a number or react to pick a track"))
+            })
+        })
+        .await?;
+
+    // Reactions are added in the background so a quick reply isn't held up by rate limits.
+    let http = ctx.http.clone();
+    let react_msg = results_msg.clone();
+    let result_count = results.len();
+    tokio::spawn(async move {
+        for number in 1..=result_count {
+            if react_msg.react(&http, number_emoji(number)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let author_id = msg.author.id;
+    let choice = tokio::select! {
+        reply = msg
+            .channel_id
+            .await_reply(ctx)
+            .author_id(author_id)
+            .filter(|reply| {
+                let content = reply.content.trim();
+                content.parse::<usize>().is_ok() || content.eq_ignore_ascii_case("cancel")
+            })
+            .timeout(SELECTION_TIMEOUT) => {
+            reply.and_then(|reply| reply.content.trim().parse::<usize>().ok())
+        }
+        reaction = results_msg
+            .await_reaction(ctx)
+            .author_id(author_id)
+            .timeout(SELECTION_TIMEOUT) => {
+            reaction.and_then(|action| emoji_number(&action.as_inner_ref().emoji))
+        }
+    };
+
+    let _ = results_msg.delete(ctx).await;
+
+    match choice
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|index| results.get(index))
+    {
+        Some(entry) => Ok(Some(entry.watch_url())),
+        None => {
+            check_msg(msg.channel_id.say(&ctx.http, "No track selected").await);
+
+            Ok(None)
+        }
+    }
+}
+
+fn number_emoji(number: usize) -> ReactionType {
+    ReactionType::Unicode(format!("{}\u{fe0f}\u{20e3}", number))
+}
+
+fn emoji_number(emoji: &ReactionType) -> Option<usize> {
+    match emoji {
+        ReactionType::Unicode(name) if name.ends_with('\u{20e3}') => name
+            .chars()
+            .next()
+            .and_then(|digit| digit.to_digit(10))
+            .map(|digit| digit as usize),
+        _ => None,
+    }
+}
+
+/// Marks tracks whose "Now playing" announcement has already been posted.
+struct Announced;
+
+impl TypeMapKey for Announced {
+    type Value = ();
+}
+
+/// Posts a "Now playing" embed for `handle` in the channel it was requested from, unless it
+/// has been announced already.
+pub async fn announce_now_playing(ctx: &Context, handle: &TrackHandle) {
+    {
+        let mut typemap = handle.typemap().write().await;
+        if typemap.get::<Announced>().is_some() {
+            return;
+        }
+        typemap.insert::<Announced>(());
+    }
+
+    let data = match music::track_data(handle).await {
+        Some(data) => data,
+        None => return,
+    };
+
+    let embed = track_embed("Now playing", handle, Some(&data));
+    check_msg(
+        data.channel
+            .send_message(&ctx.http, |m| m.set_embed(embed))
+            .await,
+    );
 }
 
+#[derive(Clone)]
 struct SongEndNotifier {
+    ctx: Context,
+    guild_id: GuildId,
     chan_id: ChannelId,
-    http: Arc<Http>,
+}
+
+/// Marks a track queued again after its source failed, so it is only retried once.
+struct Retried;
+
+impl TypeMapKey for Retried {
+    type Value = ();
+}
+
+impl SongEndNotifier {
+    /// Reports a track whose source broke off and, unless it is already a retry, queues it
+    /// again to play next from where it stopped.
+    async fn recover(&self, handle: &TrackHandle, position: Duration) {
+        let title = handle
+            .metadata()
+            .title
+            .clone()
+            .unwrap_or_else(|| "Unknown title".to_string());
+        let retried = handle.typemap().read().await.get::<Retried>().is_some();
+        let retry_failed = guild_settings(self.guild_id).await.retry_failed;
+
+        let retrying = match music::track_data(handle).await {
+            Some(data) if retry_failed && !retried => {
+                retry(&self.ctx, self.guild_id, data, position).await
+            }
+            _ => false,
+        };
+
+        check_msg(
+            self.chan_id
+                .say(
+                    &self.ctx.http,
+                    format!(
+                        "**{}** stopped at {} because its source failed, {}",
+                        title,
+                        format_duration(position),
+                        if retrying {
+                            "retrying it next"
+                        } else {
+                            "skipping it"
+                        }
+                    ),
+                )
+                .await,
+        );
+    }
 }
 
 #[async_trait]
 impl VoiceEventHandler for SongEndNotifier {
-    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
-        check_msg(self.chan_id.say(&self.http, "Song finished playing!").await);
+    async fn act(&self, event_ctx: &EventContext<'_>) -> Option<Event> {
+        let ended = match event_ctx {
+            EventContext::Track(track_list) => track_list
+                .first()
+                .map(|(state, handle)| (state.playing.clone(), state.position, (*handle).clone())),
+            _ => None,
+        };
+
+        // Retrying, requeueing and autoplay can all wait on yt-dlp, and songbird holds back
+        // the driver's other events until this returns.
+        let notifier = self.clone();
+        tokio::spawn(async move { notifier.track_ended(ended).await });
 
         None
     }
 }
+
+impl SongEndNotifier {
+    /// Records, retries or requeues the track that ended, as its play mode and position call
+    /// for, then announces what plays next.
+    async fn track_ended(&self, ended: Option<(PlayMode, Duration, TrackHandle)>) -> Option<()> {
+        let mut finished = false;
+
+        if let Some((playing, position, handle)) = ended {
+            finished = matches!(playing, PlayMode::End);
+
+            // Tracks removed from the queue before they started never played, so they are
+            // left out of the history unless they were skipped.
+            let skipped = handle
+                .typemap()
+                .read()
+                .await
+                .contains_key::<history::Skipped>();
+
+            if finished || skipped || !position.is_zero() {
+                if let Some(data) = music::track_data(&handle).await {
+                    history::record(self.guild_id, &handle, data).await;
+                }
+            }
+
+            // Sped up tracks take less time to play than their listed length.
+            let speed = get_filter(self.guild_id).await.speed();
+
+            // Songbird has no error event for tracks, so a source that dies mid-stream shows
+            // up as a track ending well before its length. Radio streams have no end, so one
+            // ending at all means it dropped. Other tracks without a known length can't be
+            // told apart from ones that finished.
+            let failed = finished
+                && match handle.metadata().duration {
+                    Some(duration) => position + FAILURE_MARGIN < duration.div_f32(speed),
+                    None => music::track_data(&handle)
+                        .await
+                        .map_or(false, |data| data.url.starts_with(RADIO_PREFIX)),
+                };
+
+            // Only tracks that played to the end go around again, so skipping a track while
+            // looping the queue drops it from the rotation.
+            if failed {
+                self.recover(&handle, position).await;
+            } else if finished && get_loop_mode(self.guild_id).await == LoopMode::Queue {
+                if let Some(data) = music::track_data(&handle).await {
+                    requeue(&self.ctx, self.guild_id, data).await;
+                }
+            }
+        }
+
+        // The queue has already moved on by the time this runs, so whatever is at its head
+        // now is the next track. Removing an upcoming track leaves the head as it was, and
+        // that has been announced already.
+        let manager = songbird::get(&self.ctx).await?;
+        let current = manager.get(self.guild_id)?.lock().await.queue().current();
+
+        match current {
+            Some(current) => announce_now_playing(&self.ctx, &current).await,
+            None if finished => {
+                if get_autoplay(self.guild_id).await
+                    && queue_related(&self.ctx, self.guild_id, self.chan_id).await
+                {
+                    return None;
+                }
+
+                check_msg(
+                    self.chan_id
+                        .say(&self.ctx.http, "Reached the end of the queue")
+                        .await,
+                );
+            }
+            None => {}
+        }
+
+        None
+    }
+}
+
+/// Queues and announces a track related to what was played recently, for autoplay. Returns
+/// whether a track was queued.
+async fn queue_related(ctx: &Context, guild_id: GuildId, chan_id: ChannelId) -> bool {
+    let url = match autoplay::pick_related(guild_id).await {
+        Some(url) => url,
+        None => return false,
+    };
+
+    let source = match source::open(guild_id, url.clone()).await {
+        Ok(source) => source,
+        Err(why) => {
+            println!("Err starting autoplay source {}: {:?}", url, why);
+            return false;
+        }
+    };
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return false,
+    };
+
+    // Autoplayed tracks are queued in the bot's own name.
+    let data = TrackData {
+        requester: ctx.cache.current_user_id(),
+        url,
+        channel: chan_id,
+    };
+
+    let song = {
+        let mut handler = handler_lock.lock().await;
+        enqueue(ctx, guild_id, &mut handler, source.into(), data).await
+    };
+
+    announce_now_playing(ctx, &song).await;
+
+    true
+}
+
+/// Sends a finished track to the back of the queue for queue looping.
+async fn requeue(ctx: &Context, guild_id: GuildId, data: TrackData) {
+    let source = match source::open(guild_id, data.url.clone()).await {
+        Ok(source) => source,
+        Err(why) => {
+            println!("Err requeueing {}: {:?}", data.url, why);
+            return;
+        }
+    };
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let mut handler = handler_lock.lock().await;
+        enqueue(ctx, guild_id, &mut handler, source.into(), data).await;
+    }
+}
+
+/// Queues a fresh source for a failed track right after the one now playing, seeking it back
+/// to `position`. Returns whether the source could be started again.
+async fn retry(ctx: &Context, guild_id: GuildId, data: TrackData, position: Duration) -> bool {
+    let source = match source::open(guild_id, data.url.clone()).await {
+        Ok(source) => source,
+        Err(why) => {
+            println!("Err retrying {}: {:?}", data.url, why);
+            return false;
+        }
+    };
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return false,
+    };
+
+    let mut handler = handler_lock.lock().await;
+    let song = enqueue(ctx, guild_id, &mut handler, source.into(), data).await;
+    song.typemap().write().await.insert::<Retried>(());
+    let _ = song.seek_time(position);
+    play_next(&handler, &song).await;
+
+    true
+}
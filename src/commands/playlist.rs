@@ -0,0 +1,288 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::commands::play::{announce_current, enqueue_urls};
+use crate::music::track_data;
+use crate::playlists::{update_playlists, user_playlists, Playlist, SavedTrack};
+use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+
+const USAGE: &str = "Usage: `~playlist save|load|delete|share|unshare <name>`, \
+                     `~playlist rename \"<name>\" \"<new name>\"`, `~playlist list [@user]`. \
+                     Add `@user` to `load` to play someone else's shared playlist.";
+
+#[command]
+#[only_in(guilds)]
+#[aliases(pl)]
+pub async fn playlist(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    if action == "list" {
+        return list_playlists(ctx, msg).await;
+    }
+
+    // Renaming takes two names, which have to be quoted if they're more than one word. Every
+    // other action takes the rest of the message as the name.
+    let name = if action == "rename" {
+        args.single_quoted::<String>().ok()
+    } else {
+        playlist_name(args.rest())
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => {
+            check_msg(msg.channel_id.say(&ctx.http, USAGE).await);
+
+            return Ok(());
+        }
+    };
+
+    let guild_id = msg.guild_id.unwrap();
+
+    match action.as_str() {
+        "save" => save_playlist(ctx, msg, name).await,
+        "load" => load_playlist(ctx, msg, name).await,
+        "delete" | "remove" => {
+            let deleted =
+                update_playlists(msg.author.id, |playlists| playlists.remove(&name).is_some())
+                    .await;
+
+            let reply = if deleted {
+                format!("Deleted playlist **{}**", name)
+            } else {
+                format!("You have no playlist called **{}**", name)
+            };
+
+            check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+            Ok(())
+        }
+        "rename" => {
+            let new_name = match args.single_quoted::<String>() {
+                Ok(new_name) => new_name,
+                Err(_) => {
+                    check_msg(msg.channel_id.say(&ctx.http, USAGE).await);
+
+                    return Ok(());
+                }
+            };
+
+            let reply = update_playlists(msg.author.id, |playlists| {
+                if playlists.contains_key(&new_name) {
+                    return format!("You already have a playlist called **{}**", new_name);
+                }
+
+                match playlists.remove(&name) {
+                    Some(playlist) => {
+                        playlists.insert(new_name.clone(), playlist);
+                        format!("Renamed **{}** to **{}**", name, new_name)
+                    }
+                    None => format!("You have no playlist called **{}**", name),
+                }
+            })
+            .await;
+
+            check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+            Ok(())
+        }
+        "share" | "unshare" => {
+            let shared = action == "share";
+
+            // Playlists are only shared with the guild they were shared from.
+            let found = update_playlists(msg.author.id, |playlists| {
+                playlists
+                    .get_mut(&name)
+                    .map(|playlist| {
+                        if shared {
+                            playlist.shared_in.insert(guild_id);
+                        } else {
+                            playlist.shared_in.remove(&guild_id);
+                        }
+                    })
+                    .is_some()
+            })
+            .await;
+
+            let reply = match (found, shared) {
+                (false, _) => format!("You have no playlist called **{}**", name),
+                (true, true) => format!(
+                    "**{}** is now shared in this server, others can play it with `~playlist load {} @{}`",
+                    name, name, msg.author.name
+                ),
+                (true, false) => format!("**{}** is no longer shared in this server", name),
+            };
+
+            check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+            Ok(())
+        }
+        _ => {
+            check_msg(msg.channel_id.say(&ctx.http, USAGE).await);
+
+            Ok(())
+        }
+    }
+}
+
+/// Saves every track in the queue, including the one playing, under `name`.
+async fn save_playlist(ctx: &Context, msg: &Message, name: String) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let queue = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock.lock().await.queue().current_queue(),
+        None => Vec::new(),
+    };
+
+    let mut tracks = Vec::with_capacity(queue.len());
+    for handle in &queue {
+        if let Some(data) = track_data(handle).await {
+            tracks.push(SavedTrack {
+                url: data.url,
+                title: handle
+                    .metadata()
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| "Unknown title".to_string()),
+            });
+        }
+    }
+
+    if tracks.is_empty() {
+        check_msg(msg.channel_id.say(&ctx.http, "The queue is empty.").await);
+
+        return Ok(());
+    }
+
+    let count = tracks.len();
+    update_playlists(msg.author.id, |playlists| {
+        let shared_in = playlists
+            .remove(&name)
+            .map(|playlist| playlist.shared_in)
+            .unwrap_or_default();
+        playlists.insert(name.clone(), Playlist { tracks, shared_in });
+    })
+    .await;
+
+    check_msg(
+        msg.channel_id
+            .say(
+                &ctx.http,
+                format!("Saved playlist **{}** with {} tracks", name, count),
+            )
+            .await,
+    );
+
+    Ok(())
+}
+
+/// Enqueues one of the author's playlists, or one the first mentioned member shared in this
+/// guild.
+async fn load_playlist(ctx: &Context, msg: &Message, name: String) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let owner = msg.mentions.first().map_or(msg.author.id, |user| user.id);
+
+    let playlist = match user_playlists(owner).await.remove(&name) {
+        Some(playlist) if owner == msg.author.id || playlist.is_shared_in(guild_id) => playlist,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("No playlist called **{}** to load", name),
+                    )
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    let urls = playlist.tracks.into_iter().map(|track| track.url).collect();
+    let enqueued = enqueue_urls(ctx, msg, &handler_lock, urls).await;
+
+    check_msg(
+        msg.channel_id
+            .say(&ctx.http, format!("Loaded **{}**: {}", name, enqueued))
+            .await,
+    );
+
+    announce_current(ctx, &handler_lock).await;
+
+    Ok(())
+}
+
+/// Lists the author's playlists, or the ones the first mentioned member shared in this guild.
+async fn list_playlists(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let owner = msg.mentions.first().unwrap_or(&msg.author);
+    let own = owner.id == msg.author.id;
+
+    let lines: Vec<String> = user_playlists(owner.id)
+        .await
+        .into_iter()
+        .filter(|(_, playlist)| own || playlist.is_shared_in(guild_id))
+        .map(|(name, playlist)| {
+            format!(
+                "**{}** - {} tracks{}",
+                name,
+                playlist.tracks.len(),
+                if own && playlist.is_shared_in(guild_id) {
+                    " (shared)"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect();
+
+    if lines.is_empty() {
+        let reply = if own {
+            "You have no saved playlists".to_string()
+        } else {
+            format!("{} has no playlists shared in this server", owner.name)
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+        return Ok(());
+    }
+
+    msg.channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("{}'s playlists", owner.name))
+                    .description(lines.join("\n"))
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// The playlist name given to a command, leaving out any `@user` mention. Quotes around it
+/// are optional.
+fn playlist_name(rest: &str) -> Option<String> {
+    let name = rest
+        .split_whitespace()
+        .filter(|word| !(word.starts_with("<@") && word.ends_with('>')))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let name = name.trim_matches('"').trim();
+
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,136 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::commands::play::{enqueue_url, play_next, resolve_query, Placement};
+use crate::music::{is_dj, is_requester};
+use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+use crate::ytdl;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(playtop)]
+pub async fn playnext(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let query = args.rest().trim();
+
+    if query.is_empty() {
+        check_msg(
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "Usage: ~playnext <URL or search>, or ~playnext <position> to move up a queued track",
+                )
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    if let Ok(position) = query.parse::<usize>() {
+        return move_up(ctx, msg, position).await;
+    }
+
+    // Everyone else's tracks get pushed back, so only DJs can jump the queue with new ones.
+    if !is_dj(ctx, msg).await {
+        check_msg(
+            msg.reply(
+                ctx,
+                "Only DJs can add tracks to the front of the queue, but you can move up one of your own with ~playnext <position>",
+            )
+            .await,
+        );
+
+        return Ok(());
+    }
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    let url = match resolve_query(ctx, msg, query).await? {
+        Some(url) => url,
+        None => return Ok(()),
+    };
+
+    if ytdl::is_playlist_url(&url) {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Playlists can only be added with ~play")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    enqueue_url(ctx, msg, &handler_lock, url, Placement::Next).await
+}
+
+/// Moves the track at `position` in the queue to straight after the one that's playing, if
+/// the author is a DJ or requested it.
+async fn move_up(ctx: &Context, msg: &Message, position: usize) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Not in a voice channel to play in")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let dj = is_dj(ctx, msg).await;
+    let handler = handler_lock.lock().await;
+
+    // Position 0 is the playing track, which can't be moved.
+    let track = match handler.queue().current_queue().into_iter().nth(position) {
+        Some(track) if position > 0 => track,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "That position isn't in the queue")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    if !dj && !is_requester(&track, msg.author.id).await {
+        check_msg(
+            msg.reply(ctx, "You can only move up tracks you queued")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let title = track
+        .metadata()
+        .title
+        .clone()
+        .unwrap_or_else(|| "Unknown title".to_string());
+
+    let reply = if play_next(&handler, &track).await {
+        format!("{} will play next", title)
+    } else {
+        "That track is no longer in the queue".to_string()
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
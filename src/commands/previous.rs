@@ -0,0 +1,95 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use songbird::input::Input;
+
+use crate::commands::play::{announce_now_playing, enqueue, limit_reason, play_now};
+use crate::history;
+use crate::music::{guild_settings, has_dj_role, is_dj, TrackData};
+use crate::source;
+use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(prev, back)]
+pub async fn previous(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let entry = match history::recent(guild_id).await.into_iter().next() {
+        Some(entry) => entry,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Nothing has been played yet")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    // Going back jumps the queue, so it's limited like `~playnext`.
+    if entry.requester != msg.author.id && !is_dj(ctx, msg).await {
+        check_msg(
+            msg.reply(ctx, "Only DJs can bring back tracks someone else queued")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    let source = match source::open(guild_id, entry.url.clone()).await {
+        Ok(source) => source,
+        Err(why) => {
+            println!("Err starting source: {:?}", why);
+
+            check_msg(msg.channel_id.say(&ctx.http, "Error sourcing ffmpeg").await);
+
+            return Ok(());
+        }
+    };
+
+    let source: Input = source.into();
+    let exempt = has_dj_role(ctx, msg).await;
+    let settings = guild_settings(guild_id).await;
+
+    let mut handler = handler_lock.lock().await;
+
+    if !exempt {
+        let length = source.metadata.duration;
+
+        if let Some(reason) = limit_reason(&handler, &settings, entry.requester, length).await {
+            drop(handler);
+            check_msg(msg.reply(ctx, reason).await);
+
+            return Ok(());
+        }
+    }
+
+    let data = TrackData {
+        requester: entry.requester,
+        url: entry.url.clone(),
+        channel: msg.channel_id,
+    };
+    let song = enqueue(ctx, guild_id, &mut handler, source, data).await;
+
+    // Whatever is playing is paused rather than stopped, and picks up again afterwards.
+    play_now(&handler, &song).await;
+    drop(handler);
+
+    // Taken out of the history, so going back again reaches the track before it.
+    history::forget_latest(guild_id, &entry).await;
+
+    announce_now_playing(ctx, &song).await;
+
+    Ok(())
+}
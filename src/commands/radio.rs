@@ -0,0 +1,35 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::commands::play::{enqueue_url, Placement};
+use crate::source::RADIO_PREFIX;
+use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(stream)]
+pub async fn radio(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let stream_url = args.rest().trim();
+
+    if !stream_url.starts_with("http") {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Must provide the URL of a radio or HLS stream")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    let url = format!("{}{}", RADIO_PREFIX, stream_url);
+    enqueue_url(ctx, msg, &handler_lock, url, Placement::Back).await
+}
@@ -0,0 +1,103 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{is_dj, is_requester};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let position = match args.single::<usize>() {
+        Ok(position) if position > 0 => position,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Must provide the position of a queued track")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let dj = is_dj(ctx, msg).await;
+        let handler = handler_lock.lock().await;
+        let queue = handler.queue();
+
+        // Position 0 is the track that's playing, so positions line up with `~list`.
+        let track = match queue.current_queue().get(position) {
+            Some(track) => track.clone(),
+            None => {
+                check_msg(
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            format!("There is no track at position {}", position),
+                        )
+                        .await,
+                );
+
+                return Ok(());
+            }
+        };
+
+        if !dj && !is_requester(&track, msg.author.id).await {
+            check_msg(
+                msg.reply(ctx, "You can only remove tracks you queued yourself")
+                    .await,
+            );
+
+            return Ok(());
+        }
+
+        let removed = queue.modify_queue(|tracks| match tracks.get(position) {
+            Some(queued) if queued.uuid() == track.uuid() => tracks.remove(position),
+            _ => None,
+        });
+
+        match removed {
+            Some(removed) => {
+                let _ = removed.stop();
+
+                let title = track
+                    .metadata()
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| "Unknown title".to_string());
+
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, format!("Removed {} from the queue", title))
+                        .await,
+                );
+            }
+            None => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, "The queue changed, try again")
+                        .await,
+                );
+            }
+        }
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
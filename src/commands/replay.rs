@@ -0,0 +1,45 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::commands::play::{enqueue_url, Placement};
+use crate::history::recent;
+use crate::utils::check_msg;
+use crate::voice::ensure_voice;
+
+#[command]
+#[only_in(guilds)]
+pub async fn replay(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let entries = recent(guild_id).await;
+
+    // Numbers match `~history`, where 1 is the most recently played track.
+    let entry = match args
+        .single::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|index| entries.get(index))
+    {
+        Some(entry) => entry.clone(),
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Must give the number of a track from `~history`")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
+    enqueue_url(ctx, msg, &handler_lock, entry.url, Placement::Back).await
+}
@@ -0,0 +1,36 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::library;
+use crate::music::is_dj;
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(reindex)]
+pub async fn rescan(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    if !is_dj(ctx, msg).await {
+        check_msg(
+            msg.reply(ctx, "Only DJs can rescan the music directory")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    let count = library::rescan().await;
+
+    check_msg(
+        msg.channel_id
+            .say(
+                &ctx.http,
+                format!("Indexed {} files in the music directory", count),
+            )
+            .await,
+    );
+
+    Ok(())
+}
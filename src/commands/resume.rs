@@ -0,0 +1,63 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, CommandResult},
+    model::channel::Message,
+};
+
+use songbird::tracks::PlayMode;
+
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(unpause)]
+pub async fn resume(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let handler = handler_lock.lock().await;
+        let queue = handler.queue();
+
+        let current = match queue.current() {
+            Some(current) => current,
+            None => {
+                check_msg(msg.channel_id.say(&ctx.http, "Nothing is playing").await);
+
+                return Ok(());
+            }
+        };
+
+        let reply = match current.get_info().await.map(|state| state.playing) {
+            Ok(PlayMode::Pause) => {
+                queue.resume()?;
+
+                format!(
+                    "Resumed {}",
+                    current
+                        .metadata()
+                        .title
+                        .as_deref()
+                        .unwrap_or("the current track")
+                )
+            }
+            Ok(PlayMode::Play) => "Already playing".to_string(),
+            _ => "Nothing is playing".to_string(),
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{guild_settings, is_dj, update_guild_settings};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn retry(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(
+            msg.reply(ctx, "Only DJs can change how failed tracks are handled")
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    // A bare `~retry` toggles it.
+    let enabled = match args.single::<String>() {
+        Ok(state) => match state.to_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => {
+                check_msg(
+                    msg.channel_id
+                        .say(&ctx.http, "Retrying must be on or off")
+                        .await,
+                );
+
+                return Ok(());
+            }
+        },
+        Err(_) => !guild_settings(guild_id).await.retry_failed,
+    };
+
+    update_guild_settings(guild_id, |settings| settings.retry_failed = enabled).await;
+
+    let reply = if enabled {
+        "Tracks whose source fails will be retried once from where they stopped"
+    } else {
+        "Tracks whose source fails will be skipped"
+    };
+
+    check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{format_duration, parse_timestamp};
+use crate::utils::check_msg;
+
+const DEFAULT_SEEK_STEP: u64 = 10;
+
+/// How a seek command moves the playhead.
+enum SeekTarget {
+    To(Duration),
+    Forward(Duration),
+    Back(Duration),
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn seek(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    match parse_timestamp(args.rest()) {
+        Some(position) => seek_current(ctx, msg, SeekTarget::To(position)).await,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Must provide a timestamp, e.g. ~seek 1:23")
+                    .await,
+            );
+
+            Ok(())
+        }
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+#[aliases(ff)]
+pub async fn forward(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let step = args.single::<u64>().unwrap_or(DEFAULT_SEEK_STEP);
+
+    seek_current(ctx, msg, SeekTarget::Forward(Duration::from_secs(step))).await
+}
+
+#[command]
+#[only_in(guilds)]
+#[aliases(rw)]
+pub async fn rewind(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let step = args.single::<u64>().unwrap_or(DEFAULT_SEEK_STEP);
+
+    seek_current(ctx, msg, SeekTarget::Back(Duration::from_secs(step))).await
+}
+
+/// Seeks the playing track, restarting its source at the new position.
+async fn seek_current(ctx: &Context, msg: &Message, target: SeekTarget) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Not in a voice channel to play in")
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    let current = handler_lock.lock().await.queue().current();
+
+    let current = match current {
+        // Live streams report no length and can only be played from where they are now.
+        Some(current) if current.is_seekable() && current.metadata().duration.is_some() => current,
+        Some(_) => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, "Can't seek in this track")
+                    .await,
+            );
+
+            return Ok(());
+        }
+        None => {
+            check_msg(msg.channel_id.say(&ctx.http, "Nothing is playing").await);
+
+            return Ok(());
+        }
+    };
+
+    let position = current.get_info().await?.position;
+
+    // Steps are whatever number was typed, so adding one can overflow.
+    let new_position = match target {
+        SeekTarget::To(position) => Some(position),
+        SeekTarget::Forward(step) => position.checked_add(step),
+        SeekTarget::Back(step) => Some(position.saturating_sub(step)),
+    };
+
+    // Only tracks with a length get this far.
+    let duration = current.metadata().duration.unwrap_or_default();
+
+    let new_position = match new_position {
+        Some(new_position) if new_position < duration => new_position,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("The track is only {} long", format_duration(duration)),
+                    )
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    current.seek_time(new_position)?;
+
+    check_msg(
+        msg.channel_id
+            .say(
+                &ctx.http,
+                format!("Seeked to {}", format_duration(new_position)),
+            )
+            .await,
+    );
+
+    Ok(())
+}
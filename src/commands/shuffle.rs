@@ -0,0 +1,57 @@
+use rand::seq::SliceRandom;
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::is_dj;
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn shuffle(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can shuffle the queue").await);
+
+        return Ok(());
+    }
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let handler = handler_lock.lock().await;
+
+        // The track at the front is the one playing, so only the rest of the queue moves.
+        let shuffled = handler.queue().modify_queue(|tracks| {
+            if tracks.len() < 3 {
+                return false;
+            }
+
+            tracks.make_contiguous()[1..].shuffle(&mut rand::thread_rng());
+            true
+        });
+
+        let reply = if shuffled {
+            "Shuffled the queue"
+        } else {
+            "Not enough tracks in the queue to shuffle"
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
@@ -1,10 +1,17 @@
+use std::env;
+
 use serenity::{
     client::Context,
     framework::standard::{macros::command, Args, CommandResult},
-    model::channel::Message,
+    model::{channel::Message, prelude::ChannelId},
 };
 
+use crate::history::Skipped;
+use crate::music::{add_skip_vote, clear_skip_votes, has_dj_role, is_requester};
 use crate::utils::check_msg;
+use crate::voice::{author_voice_channel, humans_in_channel};
+
+const DEFAULT_SKIP_VOTE_RATIO: f64 = 0.5;
 
 #[command]
 #[only_in(guilds)]
@@ -18,8 +25,62 @@ pub async fn skip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
         .clone();
 
     if let Some(handler_lock) = manager.get(guild_id) {
+        // Checking the role can fetch the member over HTTP, so it's done before locking the
+        // call.
+        let dj = has_dj_role(ctx, msg).await;
         let handler = handler_lock.lock().await;
         let queue = handler.queue();
+
+        let current = match queue.current() {
+            Some(current) => current,
+            None => {
+                check_msg(msg.channel_id.say(&ctx.http, "Nothing to skip").await);
+
+                return Ok(());
+            }
+        };
+
+        // The requester and DJs skip straight away; everyone else has to win a vote among
+        // the people listening.
+        let force = dj || is_requester(&current, msg.author.id).await;
+
+        if !force {
+            let channel_id = match handler.current_channel() {
+                Some(channel) => ChannelId(channel.0),
+                None => return Ok(()),
+            };
+
+            if author_voice_channel(ctx, msg) != Some(channel_id) {
+                check_msg(
+                    msg.reply(ctx, format!("You need to be in <#{}> to vote", channel_id))
+                        .await,
+                );
+
+                return Ok(());
+            }
+
+            let listeners = humans_in_channel(ctx, guild_id, channel_id);
+            let required = ((listeners as f64 * skip_vote_ratio()).ceil() as usize).max(1);
+            let votes = add_skip_vote(guild_id, &current, msg.author.id).await;
+
+            if votes < required {
+                check_msg(
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            format!("Voted to skip: {}/{} votes", votes, required),
+                        )
+                        .await,
+                );
+
+                return Ok(());
+            }
+        }
+
+        clear_skip_votes(guild_id).await;
+        // Skipped tracks are kept in the history even if they hadn't started, so
+        // `~previous` can bring them back.
+        current.typemap().write().await.insert::<Skipped>(());
         let _ = queue.skip();
 
         check_msg(
@@ -40,3 +101,13 @@ pub async fn skip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
 
     Ok(())
 }
+
+/// The fraction of listeners that must vote before a track is skipped, from
+/// `SKIP_VOTE_RATIO`.
+fn skip_vote_ratio() -> f64 {
+    env::var("SKIP_VOTE_RATIO")
+        .ok()
+        .and_then(|ratio| ratio.parse().ok())
+        .filter(|ratio| (0.0..=1.0).contains(ratio))
+        .unwrap_or(DEFAULT_SKIP_VOTE_RATIO)
+}
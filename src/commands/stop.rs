@@ -0,0 +1,59 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{clear_skip_votes, is_dj};
+use crate::utils::check_msg;
+
+#[command]
+#[only_in(guilds)]
+pub async fn stop(ctx: &Context, msg: &Message) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    if !is_dj(ctx, msg).await {
+        check_msg(msg.reply(ctx, "Only DJs can stop the music").await);
+
+        return Ok(());
+    }
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let handler = handler_lock.lock().await;
+        let queue = handler.queue();
+
+        if queue.is_empty() {
+            check_msg(msg.channel_id.say(&ctx.http, "Nothing is playing").await);
+
+            return Ok(());
+        }
+
+        // Stopping drops every queued track but keeps the bot in the voice channel.
+        let cleared = queue.len();
+        queue.stop();
+        clear_skip_votes(guild_id).await;
+
+        check_msg(
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("Stopped playback and cleared {} track(s)", cleared),
+                )
+                .await,
+        );
+    } else {
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, "Not in a voice channel to play in")
+                .await,
+        );
+    }
+
+    Ok(())
+}
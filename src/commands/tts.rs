@@ -9,10 +9,14 @@ use serenity::{
     framework::standard::{macros::command, Args, CommandResult},
     model::channel::Message,
 };
+use songbird::create_player;
 
+use crate::music::{guild_settings, volume_multiplier};
 use crate::utils::check_msg;
+use crate::voice::ensure_voice;
 
 #[command]
+#[only_in(guilds)]
 pub async fn tts(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let text = match args.rest().is_empty() {
         true => {
@@ -22,6 +26,12 @@ pub async fn tts(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
         false => args.rest(),
     };
 
+    let guild_id = msg.guild_id.unwrap();
+    let handler_lock = match ensure_voice(ctx, msg).await {
+        Some(handler_lock) => handler_lock,
+        None => return Ok(()),
+    };
+
     // TODO make this into its own function
     let speech_key = env::var("SPEECH_KEY").expect("token");
     let speech_region = env::var("SPEECH_REGION").expect("token");
@@ -55,39 +65,28 @@ pub async fn tts(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
             output_file.write_all(&bytes)?;
             drop(output_file);
 
-            let guild_id = msg.guild_id.unwrap();
-            let manager = songbird::get(ctx)
-                .await
-                .expect("Songbird Voice client placed in initialisation.")
-                .clone();
-
-            if let Some(handler_lock) = manager.get(guild_id) {
-                let mut handler = handler_lock.lock().await;
+            let mut handler = handler_lock.lock().await;
 
-                match songbird::ffmpeg("./output.mp3").await {
-                    Ok(source) => {
-                        println!("Playing output.mp3 in the voice channel");
-                        let track_handle = handler.play_source(source.into());
-                        if let Err(e) = track_handle.play() {
-                            println!("Error during playback: {:?}", e);
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error playing output.mp3: {:?}", e);
-                        check_msg(
-                            msg.channel_id
-                                .say(&ctx.http, "Error playing audio in the voice channel")
-                                .await,
-                        );
+            match songbird::ffmpeg("./output.mp3").await {
+                Ok(source) => {
+                    println!("Playing output.mp3 in the voice channel");
+                    // Set on the track before it plays, so no audio goes out at the default.
+                    let (mut track, track_handle) = create_player(source.into());
+                    let tts_volume = guild_settings(guild_id).await.tts_volume;
+                    track.set_volume(volume_multiplier(tts_volume));
+                    handler.play(track);
+                    if let Err(e) = track_handle.play() {
+                        println!("Error during playback: {:?}", e);
                     }
                 }
-            } else {
-                println!("No handler found for the guild");
-                check_msg(
-                    msg.channel_id
-                        .say(&ctx.http, "The bot is not connected to a voice channel")
-                        .await,
-                );
+                Err(e) => {
+                    println!("Error playing output.mp3: {:?}", e);
+                    check_msg(
+                        msg.channel_id
+                            .say(&ctx.http, "Error playing audio in the voice channel")
+                            .await,
+                    );
+                }
             }
         }
         _ => {
@@ -0,0 +1,94 @@
+use serenity::{
+    client::Context,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use crate::music::{guild_settings, update_guild_settings, volume_multiplier};
+use crate::utils::check_msg;
+
+const MAX_VOLUME: u32 = 200;
+
+#[command]
+#[only_in(guilds)]
+#[aliases(vol)]
+pub async fn volume(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild(&ctx.cache).unwrap();
+    let guild_id = guild.id;
+
+    // `~volume tts <n>` sets the speech volume, which is kept separate from music.
+    let tts = match args.current() {
+        Some(target) if target.eq_ignore_ascii_case("tts") => {
+            args.advance();
+            true
+        }
+        _ => false,
+    };
+
+    let settings = guild_settings(guild_id).await;
+
+    if args.is_empty() {
+        let reply = if tts {
+            format!("TTS volume is {}%", settings.tts_volume)
+        } else {
+            format!("Music volume is {}%", settings.volume)
+        };
+
+        check_msg(msg.channel_id.say(&ctx.http, reply).await);
+
+        return Ok(());
+    }
+
+    let percent = match args.single::<u32>() {
+        Ok(percent) if percent <= MAX_VOLUME => percent,
+        _ => {
+            check_msg(
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("Volume must be between 0 and {}", MAX_VOLUME),
+                    )
+                    .await,
+            );
+
+            return Ok(());
+        }
+    };
+
+    if tts {
+        update_guild_settings(guild_id, |settings| settings.tts_volume = percent).await;
+
+        check_msg(
+            msg.channel_id
+                .say(&ctx.http, format!("TTS volume set to {}%", percent))
+                .await,
+        );
+
+        return Ok(());
+    }
+
+    update_guild_settings(guild_id, |settings| settings.volume = percent).await;
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    // Queued tracks were created with the old volume, so they're all updated along with the
+    // one that's playing.
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let tracks = handler_lock.lock().await.queue().current_queue();
+
+        for track in tracks {
+            let _ = track.set_volume(volume_multiplier(percent));
+        }
+    }
+
+    check_msg(
+        msg.channel_id
+            .say(&ctx.http, format!("Music volume set to {}%", percent))
+            .await,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, UserId},
+    prelude::{Mutex, TypeMapKey},
+};
+use songbird::tracks::TrackHandle;
+
+use crate::music::TrackData;
+use crate::storage;
+
+const HISTORY_FILE: &str = "history";
+/// How many played tracks are remembered per guild.
+pub const HISTORY_LIMIT: usize = 50;
+
+lazy_static! {
+    static ref HISTORY: Mutex<HashMap<GuildId, VecDeque<HistoryEntry>>> =
+        Mutex::new(storage::load(HISTORY_FILE));
+}
+
+/// A track that was played in a guild.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: String,
+    pub requester: UserId,
+    /// When the track stopped playing, in seconds since the Unix epoch.
+    pub played_at: u64,
+}
+
+/// Marks skipped tracks, which are recorded even if they hadn't started playing.
+pub struct Skipped;
+
+impl TypeMapKey for Skipped {
+    type Value = ();
+}
+
+/// Records a track that has stopped playing as the guild's most recent.
+pub async fn record(guild_id: GuildId, handle: &TrackHandle, data: TrackData) {
+    let entry = HistoryEntry {
+        url: data.url,
+        title: handle
+            .metadata()
+            .title
+            .clone()
+            .unwrap_or_else(|| "Unknown title".to_string()),
+        requester: data.requester,
+        played_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+    };
+
+    let mut history = HISTORY.lock().await;
+    let entries = history.entry(guild_id).or_default();
+    entries.push_front(entry);
+    entries.truncate(HISTORY_LIMIT);
+
+    if let Err(why) = storage::save(HISTORY_FILE, &*history) {
+        println!("Error saving history: {:?}", why);
+    }
+}
+
+/// The guild's played tracks, most recent first.
+pub async fn recent(guild_id: GuildId) -> Vec<HistoryEntry> {
+    HISTORY
+        .lock()
+        .await
+        .get(&guild_id)
+        .map(|entries| entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Removes `entry` from the guild's history if it is still the most recent, for when it has
+/// been brought back into the queue.
+pub async fn forget_latest(guild_id: GuildId, entry: &HistoryEntry) {
+    let mut history = HISTORY.lock().await;
+    let entries = match history.get_mut(&guild_id) {
+        Some(entries) => entries,
+        None => return,
+    };
+
+    let is_latest = entries.front().map_or(false, |latest| {
+        latest.url == entry.url && latest.played_at == entry.played_at
+    });
+
+    if !is_latest {
+        return;
+    }
+
+    entries.pop_front();
+
+    if let Err(why) = storage::save(HISTORY_FILE, &*history) {
+        println!("Error saving history: {:?}", why);
+    }
+}
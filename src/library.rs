@@ -0,0 +1,374 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Component, Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use futures::{stream, StreamExt};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serenity::prelude::Mutex;
+use songbird::input::Metadata;
+use tokio::process::Command;
+
+const DEFAULT_MUSIC_DIR: &str = "music";
+const AUDIO_EXTENSIONS: [&str; 6] = ["mp3", "ogg", "flac", "wav", "m4a", "opus"];
+const PROBE_CONCURRENCY: usize = 8;
+
+lazy_static! {
+    /// The indexed music directory, built the first time it is searched and updated by
+    /// `rescan`.
+    static ref LIBRARY: Mutex<Option<Vec<AudioFile>>> = Mutex::new(None);
+}
+
+/// An audio file played directly through ffmpeg, either from the music directory or from a
+/// URL such as a Discord attachment.
+#[derive(Clone, Debug)]
+pub struct AudioFile {
+    /// Path relative to the music directory, or the file's URL.
+    pub location: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl AudioFile {
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            title: Some(self.title.clone()),
+            artist: self.artist.clone(),
+            duration: self.duration,
+            source_url: self
+                .location
+                .starts_with("http")
+                .then(|| self.location.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The subset of `ffprobe -show_format` output used for tags and length.
+#[derive(Debug, Deserialize)]
+struct Probe {
+    format: ProbeFormat,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeFormat {
+    duration: Option<String>,
+    tags: ProbeTags,
+}
+
+/// Tag names vary in case between containers, so both spellings are accepted.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeTags {
+    #[serde(alias = "TITLE")]
+    title: Option<String>,
+    #[serde(alias = "ARTIST")]
+    artist: Option<String>,
+    /// The station name of an internet radio stream.
+    #[serde(rename = "icy-name")]
+    icy_name: Option<String>,
+}
+
+/// The directory `local:` tracks are played from, set with `MUSIC_DIR`.
+pub fn music_dir() -> PathBuf {
+    env::var("MUSIC_DIR")
+        .unwrap_or_else(|_| DEFAULT_MUSIC_DIR.to_string())
+        .into()
+}
+
+/// Whether a path or URL names a file ffmpeg can play without going through yt-dlp.
+pub fn is_audio_file(location: &str) -> bool {
+    let path = location.split(['?', '#']).next().unwrap_or(location);
+
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| {
+            AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        })
+}
+
+/// Reads the title, artist and length of `input` with ffprobe, titling it after its file
+/// name when it has no tags. `location` is what the returned file is known by.
+pub async fn probe(location: &str, input: &str) -> AudioFile {
+    let output = Command::new("ffprobe")
+        .args(["-v", "quiet", "-print_format", "json", "-show_format"])
+        .arg(input)
+        .stdin(Stdio::null())
+        .output()
+        .await;
+
+    let format = output
+        .ok()
+        .and_then(|output| serde_json::from_slice::<Probe>(&output.stdout).ok())
+        .map(|probe| probe.format)
+        .unwrap_or_default();
+
+    let file_name = location.split(['?', '#']).next().unwrap_or(location);
+    let fallback_title = Path::new(file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| location.to_string());
+
+    AudioFile {
+        location: location.to_string(),
+        title: format
+            .tags
+            .title
+            .or(format.tags.icy_name)
+            .unwrap_or(fallback_title),
+        artist: format.tags.artist,
+        duration: format
+            .duration
+            .and_then(|duration| duration.parse::<f64>().ok())
+            .map(Duration::from_secs_f64),
+    }
+}
+
+/// Looks up an indexed file by its path relative to the music directory.
+pub async fn find(location: &str) -> Option<AudioFile> {
+    with_index(|files| files.iter().find(|file| file.location == location).cloned()).await
+}
+
+/// Finds the indexed files best matching `query`. Every word of the query has to appear in
+/// the title, artist or path; failing that, files whose name contains the query's letters in
+/// order are returned.
+pub async fn search(query: &str) -> Vec<AudioFile> {
+    with_index(|files| best_matches(files, query)).await
+}
+
+/// The full path of a file in the music directory, refusing paths that would leave it.
+pub fn full_path(location: &str) -> Option<PathBuf> {
+    let relative = Path::new(location);
+
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| music_dir().join(relative))
+}
+
+/// Indexes the music directory again, picking up files added or removed since it was last
+/// indexed. Files already in the index aren't probed again. Returns how many are indexed.
+pub async fn rescan() -> usize {
+    let mut library = LIBRARY.lock().await;
+
+    let files = index(library.take().unwrap_or_default()).await;
+    let count = files.len();
+    *library = Some(files);
+
+    count
+}
+
+async fn with_index<T>(f: impl FnOnce(&[AudioFile]) -> T) -> T {
+    let mut library = LIBRARY.lock().await;
+
+    if library.is_none() {
+        let files = index(Vec::new()).await;
+        println!("Indexed {} files in the music directory", files.len());
+        *library = Some(files);
+    }
+
+    f(library.as_deref().unwrap_or_default())
+}
+
+/// Walks the music directory and probes every audio file in it that isn't among `known`.
+async fn index(known: Vec<AudioFile>) -> Vec<AudioFile> {
+    let mut known: HashMap<String, AudioFile> = known
+        .into_iter()
+        .map(|file| (file.location.clone(), file))
+        .collect();
+
+    let root = music_dir();
+    let paths = tokio::task::spawn_blocking(move || {
+        let mut paths = Vec::new();
+        collect_audio_files(&root, &root, &mut paths);
+        paths
+    })
+    .await
+    .unwrap_or_default();
+
+    let (indexed, new): (Vec<_>, Vec<_>) = paths
+        .into_iter()
+        .partition(|relative| known.contains_key(relative));
+
+    let mut files: Vec<AudioFile> = indexed
+        .iter()
+        .filter_map(|relative| known.remove(relative))
+        .collect();
+
+    let probed: Vec<AudioFile> = stream::iter(new)
+        .map(|relative| async move {
+            let full_path = music_dir().join(&relative);
+            probe(&relative, &full_path.to_string_lossy()).await
+        })
+        .buffer_unordered(PROBE_CONCURRENCY)
+        .collect()
+        .await;
+
+    files.extend(probed);
+    files
+}
+
+fn collect_audio_files(root: &Path, dir: &Path, paths: &mut Vec<String>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_audio_files(root, &path, paths);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let relative = relative.to_string_lossy().into_owned();
+            if is_audio_file(&relative) {
+                paths.push(relative);
+            }
+        }
+    }
+}
+
+fn best_matches(files: &[AudioFile], query: &str) -> Vec<AudioFile> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut matches: Vec<(usize, &AudioFile)> = files
+        .iter()
+        .filter_map(|file| {
+            let haystack = haystack(file);
+            words
+                .iter()
+                .all(|word| haystack.contains(word))
+                .then(|| (haystack.len(), file))
+        })
+        .collect();
+
+    if matches.is_empty() {
+        let letters: String = words.concat();
+        matches = files
+            .iter()
+            .filter_map(|file| {
+                let haystack = haystack(file);
+                is_subsequence(&letters, &haystack).then(|| (haystack.len(), file))
+            })
+            .collect();
+    }
+
+    // Shorter names match more of the query, so they rank first.
+    matches.sort_by_key(|(length, _)| *length);
+    matches.into_iter().map(|(_, file)| file.clone()).collect()
+}
+
+fn haystack(file: &AudioFile) -> String {
+    format!(
+        "{} {} {}",
+        file.artist.as_deref().unwrap_or_default(),
+        file.title,
+        file.location
+    )
+    .to_lowercase()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(location: &str, title: &str, artist: Option<&str>) -> AudioFile {
+        AudioFile {
+            location: location.to_string(),
+            title: title.to_string(),
+            artist: artist.map(str::to_string),
+            duration: None,
+        }
+    }
+
+    fn locations(files: Vec<AudioFile>) -> Vec<String> {
+        files.into_iter().map(|file| file.location).collect()
+    }
+
+    fn library() -> Vec<AudioFile> {
+        vec![
+            file(
+                "albums/Discovery/One More Time.mp3",
+                "One More Time",
+                Some("Daft Punk"),
+            ),
+            file("misc/time.ogg", "Time", None),
+            file(
+                "albums/Homework/Around the World.flac",
+                "Around the World",
+                Some("Daft Punk"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn best_matches_needs_every_word() {
+        assert_eq!(
+            locations(best_matches(&library(), "daft TIME")),
+            vec!["albums/Discovery/One More Time.mp3"]
+        );
+        assert_eq!(
+            locations(best_matches(&library(), "homework")),
+            vec!["albums/Homework/Around the World.flac"]
+        );
+    }
+
+    #[test]
+    fn best_matches_ranks_shorter_names_first() {
+        assert_eq!(
+            locations(best_matches(&library(), "time")),
+            vec!["misc/time.ogg", "albums/Discovery/One More Time.mp3"]
+        );
+    }
+
+    #[test]
+    fn best_matches_falls_back_to_letters_in_order() {
+        assert_eq!(
+            locations(best_matches(&library(), "arnd wrld")),
+            vec!["albums/Homework/Around the World.flac"]
+        );
+        assert!(best_matches(&library(), "zzz").is_empty());
+    }
+
+    #[test]
+    fn is_subsequence_keeps_letters_in_order() {
+        assert!(is_subsequence("", "anything"));
+        assert!(is_subsequence("dp", "daft punk"));
+        assert!(!is_subsequence("pd", "daft punk"));
+        assert!(!is_subsequence("daft punks", "daft punk"));
+    }
+
+    #[test]
+    fn full_path_stays_inside_the_music_directory() {
+        assert_eq!(
+            full_path("albums/song.mp3"),
+            Some(music_dir().join("albums/song.mp3"))
+        );
+        assert_eq!(full_path("../secret.mp3"), None);
+        assert_eq!(full_path("/etc/passwd"), None);
+        assert_eq!(full_path("albums/../../secret.mp3"), None);
+    }
+
+    #[test]
+    fn is_audio_file_checks_the_extension() {
+        assert!(is_audio_file("song.MP3"));
+        assert!(is_audio_file("https://cdn.example.com/song.ogg?ex=123"));
+        assert!(!is_audio_file("video.mp4"));
+        assert!(!is_audio_file(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        ));
+    }
+}
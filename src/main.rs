@@ -1,31 +1,65 @@
+mod autoplay;
+mod cache;
 mod commands;
+mod history;
+mod library;
+mod music;
+mod playlists;
+mod radio;
+mod source;
+mod storage;
 mod utils;
+mod voice;
+mod ytdl;
 
 use crate::commands::ask::*;
+use crate::commands::autoplay::*;
+use crate::commands::cache::*;
+use crate::commands::clear::*;
+use crate::commands::fair::*;
+use crate::commands::filter::*;
+use crate::commands::history::*;
 use crate::commands::join::*;
+use crate::commands::jump::*;
 use crate::commands::leave::*;
+use crate::commands::limit::*;
+use crate::commands::list::*;
+use crate::commands::loop_mode::*;
+use crate::commands::move_track::*;
 use crate::commands::mvp::*;
+use crate::commands::np::*;
+use crate::commands::pause::*;
 use crate::commands::ping::*;
 use crate::commands::play::*;
+use crate::commands::playlist::*;
+use crate::commands::playnext::*;
+use crate::commands::previous::*;
+use crate::commands::radio::*;
+use crate::commands::remove::*;
+use crate::commands::replay::*;
+use crate::commands::rescan::*;
+use crate::commands::resume::*;
+use crate::commands::retry::*;
+use crate::commands::seek::*;
+use crate::commands::shuffle::*;
 use crate::commands::skip::*;
+use crate::commands::stop::*;
 use crate::commands::tts::*;
+use crate::commands::volume::*;
 
+use std::collections::HashSet;
 use std::env;
 
 use songbird::Config;
 use songbird::SerenityInit;
+use songbird::Songbird;
 
 use serenity::{
     async_trait,
     client::{Client, Context, EventHandler},
-    framework::{
-        standard::{
-            macros::{command, group},
-            Args, CommandResult,
-        },
-        StandardFramework,
-    },
-    model::{channel::Message, gateway::Ready},
+    framework::{standard::macros::group, StandardFramework},
+    http::Http,
+    model::{gateway::Ready, voice::VoiceState},
     prelude::GatewayIntents,
 };
 
@@ -33,15 +67,33 @@ use songbird::driver::DecodeMode;
 use tracing_subscriber;
 
 #[group]
-#[commands(ping, join, leave, play, skip, list, ask, tts, mvp)]
+#[commands(
+    ping, join, leave, play, playnext, radio, pause, resume, stop, skip, previous, list, np, seek,
+    forward, rewind, loop_mode, autoplay, shuffle, remove, move_track, clear, jump, fair, limit,
+    retry, volume, filter, history, replay, playlist, rescan, cache, ask, tts, mvp
+)]
 struct General;
 
 struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+
+        tokio::spawn(music::persist_queues(ctx));
+    }
+
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        // Only someone leaving a channel can leave the bot alone in it. The bot rejoining a
+        // saved queue's channel before anyone is back doesn't count.
+        let left = old
+            .and_then(|old| old.channel_id)
+            .filter(|channel| new.channel_id != Some(*channel));
+
+        if let (Some(guild_id), Some(left)) = (new.guild_id, left) {
+            voice::leave_if_alone(&ctx, guild_id, left).await;
+        }
     }
 }
 
@@ -49,25 +101,44 @@ impl EventHandler for Handler {
 async fn main() {
     tracing_subscriber::fmt::init();
 
+    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+
+    // The application's owner, or its team's, may use owner-only commands.
+    let owners = match Http::new(&token).get_current_application_info().await {
+        Ok(info) => {
+            let mut owners = HashSet::new();
+            match info.team {
+                Some(team) => owners.insert(team.owner_user_id),
+                None => owners.insert(info.owner.id),
+            };
+            owners
+        }
+        Err(why) => {
+            println!("Could not access application info: {:?}", why);
+            HashSet::new()
+        }
+    };
+
     let framework = StandardFramework::new()
-        .configure(|c| c.prefix("~"))
+        .configure(|c| c.owners(owners).prefix("~"))
         .group(&GENERAL_GROUP);
 
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
-
     let intents = GatewayIntents::non_privileged()
         | GatewayIntents::MESSAGE_CONTENT
         | GatewayIntents::GUILD_VOICE_STATES;
 
     let songbird_config = Config::default().decode_mode(DecodeMode::Decode);
+    let manager = Songbird::serenity_from_config(songbird_config);
 
     let mut client = Client::builder(token, intents)
         .event_handler(Handler)
         .framework(framework)
-        .register_songbird_from_config(songbird_config)
+        .register_songbird_with(manager.clone())
         .await
         .expect("Error creating client");
 
+    let cache = client.cache_and_http.cache.clone();
+
     tokio::spawn(async move {
         let _ = client
             .start()
@@ -80,42 +151,6 @@ async fn main() {
         .map_err(|why| println!("Failed to handle Ctrl-C signal: {:?}", why))
         .ok();
     println!("Received Ctrl-C, shutting down.");
-}
-
-#[command]
-#[only_in(guilds)]
-async fn list(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
-    let guild = msg.guild(&ctx.cache).unwrap();
-    let guild_id = guild.id;
-
-    let manager = songbird::get(ctx)
-        .await
-        .expect("Songbird Voice client placed in at initialisation.")
-        .clone();
-
-    if let Some(handler_lock) = manager.get(guild_id) {
-        let handler = handler_lock.lock().await;
-        let queue = handler.queue();
-
-        let queue_str = queue.current_queue();
-
-        println!("{:?}", queue_str);
-
-        utils::check_msg(
-            msg.channel_id
-                .say(
-                    &ctx.http,
-                    format!("Song List in queue printed to stdout, check your console."),
-                )
-                .await,
-        );
-    } else {
-        utils::check_msg(
-            msg.channel_id
-                .say(&ctx.http, "Not in a voice channel to play in")
-                .await,
-        );
-    }
 
-    Ok(())
+    music::save_queues(&manager, cache.guilds()).await;
 }
@@ -0,0 +1,830 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::prelude::{ChannelId, GuildId, Message, UserId},
+    prelude::{Mutex, TypeMapKey},
+};
+use songbird::{tracks::TrackHandle, Songbird};
+
+use crate::commands::play::{announce_now_playing, enqueue};
+use crate::source::{self, RADIO_PREFIX};
+use crate::storage;
+use crate::voice::join_voice;
+
+const DEFAULT_DJ_ROLE: &str = "DJ";
+const QUEUES_FILE: &str = "queues";
+const SETTINGS_FILE: &str = "settings";
+const DEFAULT_VOLUME: u32 = 100;
+const QUEUE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Set once saved queues have been restored, so that an early save can't overwrite them.
+static QUEUES_RESTORED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref GUILD_STATES: Mutex<HashMap<GuildId, GuildState>> = Mutex::new(HashMap::new());
+    static ref GUILD_SETTINGS: Mutex<HashMap<GuildId, GuildSettings>> =
+        Mutex::new(storage::load(SETTINGS_FILE));
+}
+
+/// In-memory music state for a guild.
+#[derive(Debug, Default)]
+struct GuildState {
+    loop_mode: LoopMode,
+    filter: AudioFilter,
+    /// Whether related tracks are queued when the queue runs out.
+    autoplay: bool,
+    /// The track being voted on and who has voted to skip it.
+    skip_votes: Option<(TrackHandle, HashSet<UserId>)>,
+}
+
+/// Music settings for a guild that are persisted across restarts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    /// Volume for queued music, as a percentage.
+    pub volume: u32,
+    /// Volume for `tts` and `ask` speech, as a percentage.
+    pub tts_volume: u32,
+    /// Whether upcoming tracks take turns between requesters instead of playing in order.
+    pub fair_queue: bool,
+    /// Most tracks one member may have queued at once.
+    pub max_user_tracks: Option<usize>,
+    /// Most minutes of music one member may have queued at once.
+    pub max_user_minutes: Option<u64>,
+    /// Whether a track whose source fails partway through is tried again once.
+    pub retry_failed: bool,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        GuildSettings {
+            volume: DEFAULT_VOLUME,
+            tts_volume: DEFAULT_VOLUME,
+            fair_queue: false,
+            max_user_tracks: None,
+            max_user_minutes: None,
+            retry_failed: true,
+        }
+    }
+}
+
+/// How finished tracks are handled: `Track` repeats the current track, `Queue` sends each
+/// finished track to the back of the queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    #[default]
+    Off,
+    Track,
+    Queue,
+}
+
+impl LoopMode {
+    /// The next mode when cycling with a bare `~loop`.
+    pub fn next(self) -> Self {
+        match self {
+            LoopMode::Off => LoopMode::Track,
+            LoopMode::Track => LoopMode::Queue,
+            LoopMode::Queue => LoopMode::Off,
+        }
+    }
+}
+
+impl fmt::Display for LoopMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LoopMode::Off => "off",
+            LoopMode::Track => "track",
+            LoopMode::Queue => "queue",
+        })
+    }
+}
+
+/// An ffmpeg audio filter applied to everything the guild plays.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AudioFilter {
+    #[default]
+    Off,
+    BassBoost,
+    Nightcore,
+    EightD,
+    /// Plays faster or slower without changing pitch, by the given factor.
+    Speed(f32),
+}
+
+impl AudioFilter {
+    /// Parses a preset name such as `nightcore`, or `speed <0.5-2.0>`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let filter = match words.next()?.to_lowercase().as_str() {
+            "off" | "none" => AudioFilter::Off,
+            "bassboost" | "bass" => AudioFilter::BassBoost,
+            "nightcore" => AudioFilter::Nightcore,
+            "8d" => AudioFilter::EightD,
+            "speed" => {
+                let factor = words.next()?.trim_end_matches('x').parse::<f32>().ok()?;
+                if !(0.5..=2.0).contains(&factor) {
+                    return None;
+                }
+                AudioFilter::Speed(factor)
+            }
+            _ => return None,
+        };
+
+        Some(filter)
+    }
+
+    /// How much faster than normal tracks play with this filter.
+    pub fn speed(self) -> f32 {
+        match self {
+            AudioFilter::Nightcore => 1.25,
+            AudioFilter::Speed(factor) => factor,
+            _ => 1.0,
+        }
+    }
+
+    /// The value passed to ffmpeg's `-af`, if the filter changes the audio at all.
+    pub fn ffmpeg_args(self) -> Option<String> {
+        match self {
+            AudioFilter::Off => None,
+            AudioFilter::BassBoost => Some("bass=g=10,dynaudnorm".to_string()),
+            // Resampling first means the rate change works the same whatever the source's rate.
+            AudioFilter::Nightcore => {
+                Some("aresample=48000,asetrate=48000*1.25,aresample=48000".to_string())
+            }
+            AudioFilter::EightD => Some("apulsator=hz=0.125".to_string()),
+            AudioFilter::Speed(factor) => Some(format!("atempo={}", factor)),
+        }
+    }
+}
+
+impl fmt::Display for AudioFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioFilter::Off => f.write_str("off"),
+            AudioFilter::BassBoost => f.write_str("bassboost"),
+            AudioFilter::Nightcore => f.write_str("nightcore"),
+            AudioFilter::EightD => f.write_str("8D"),
+            AudioFilter::Speed(factor) => write!(f, "speed {}x", factor),
+        }
+    }
+}
+
+/// Information about who queued a track and from where, stored in the track's typemap when
+/// it is enqueued.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TrackData {
+    pub requester: UserId,
+    pub url: String,
+    pub channel: ChannelId,
+}
+
+impl TrackData {
+    /// The track's URL if it can be linked to, which `local:` tracks can't.
+    pub fn link(&self) -> Option<&str> {
+        let url = self.url.strip_prefix(RADIO_PREFIX).unwrap_or(&self.url);
+        Some(url).filter(|url| url.starts_with("http"))
+    }
+}
+
+impl TypeMapKey for TrackData {
+    type Value = TrackData;
+}
+
+/// A guild's queue as written to disk, enough to rejoin and pick up where playback left off.
+#[derive(Debug, Deserialize, Serialize)]
+struct SavedQueue {
+    voice_channel: ChannelId,
+    tracks: Vec<TrackData>,
+    elapsed: Duration,
+}
+
+pub async fn get_loop_mode(guild_id: GuildId) -> LoopMode {
+    GUILD_STATES
+        .lock()
+        .await
+        .get(&guild_id)
+        .map(|state| state.loop_mode)
+        .unwrap_or_default()
+}
+
+pub async fn set_loop_mode(guild_id: GuildId, mode: LoopMode) {
+    GUILD_STATES
+        .lock()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .loop_mode = mode;
+}
+
+pub async fn get_filter(guild_id: GuildId) -> AudioFilter {
+    GUILD_STATES
+        .lock()
+        .await
+        .get(&guild_id)
+        .map(|state| state.filter)
+        .unwrap_or_default()
+}
+
+pub async fn set_filter(guild_id: GuildId, filter: AudioFilter) {
+    GUILD_STATES
+        .lock()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .filter = filter;
+}
+
+pub async fn get_autoplay(guild_id: GuildId) -> bool {
+    GUILD_STATES
+        .lock()
+        .await
+        .get(&guild_id)
+        .map_or(false, |state| state.autoplay)
+}
+
+pub async fn set_autoplay(guild_id: GuildId, enabled: bool) {
+    GUILD_STATES
+        .lock()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .autoplay = enabled;
+}
+
+pub async fn guild_settings(guild_id: GuildId) -> GuildSettings {
+    GUILD_SETTINGS
+        .lock()
+        .await
+        .get(&guild_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Changes a guild's settings and writes all settings back to disk.
+pub async fn update_guild_settings(guild_id: GuildId, update: impl FnOnce(&mut GuildSettings)) {
+    let mut settings = GUILD_SETTINGS.lock().await;
+    update(settings.entry(guild_id).or_default());
+
+    if let Err(why) = storage::save(SETTINGS_FILE, &*settings) {
+        println!("Error saving guild settings: {:?}", why);
+    }
+}
+
+/// Converts a volume percentage into the multiplier songbird expects.
+pub fn volume_multiplier(percent: u32) -> f32 {
+    percent as f32 / 100.0
+}
+
+/// Records `user`'s vote to skip `current` and returns how many votes it has. Votes left
+/// over from an earlier track are discarded first.
+pub async fn add_skip_vote(guild_id: GuildId, current: &TrackHandle, user: UserId) -> usize {
+    let mut states = GUILD_STATES.lock().await;
+    let state = states.entry(guild_id).or_default();
+
+    let same_track = matches!(
+        &state.skip_votes,
+        Some((track, _)) if track.uuid() == current.uuid()
+    );
+    if !same_track {
+        state.skip_votes = None;
+    }
+
+    let (_, votes) = state
+        .skip_votes
+        .get_or_insert_with(|| (current.clone(), HashSet::new()));
+    votes.insert(user);
+
+    votes.len()
+}
+
+pub async fn clear_skip_votes(guild_id: GuildId) {
+    if let Some(state) = GUILD_STATES.lock().await.get_mut(&guild_id) {
+        state.skip_votes = None;
+    }
+}
+
+/// Reads the `TrackData` stored on a track, if it was enqueued through `play`.
+pub async fn track_data(handle: &TrackHandle) -> Option<TrackData> {
+    handle.typemap().read().await.get::<TrackData>().cloned()
+}
+
+/// Whether `user` is the one who queued `handle`.
+pub async fn is_requester(handle: &TrackHandle, user: UserId) -> bool {
+    track_data(handle)
+        .await
+        .map_or(false, |data| data.requester == user)
+}
+
+/// Whether the author of `msg` may manage other people's tracks. Administrators and members
+/// with the DJ role (named by `DJ_ROLE`, "DJ" by default) can, and so can everyone in guilds
+/// that don't have that role.
+pub async fn is_dj(ctx: &Context, msg: &Message) -> bool {
+    check_dj(ctx, msg, true).await
+}
+
+/// Like `is_dj`, but only administrators and members who actually have the DJ role pass,
+/// even in guilds without one.
+pub async fn has_dj_role(ctx: &Context, msg: &Message) -> bool {
+    check_dj(ctx, msg, false).await
+}
+
+async fn check_dj(ctx: &Context, msg: &Message, open_without_role: bool) -> bool {
+    let guild = match msg.guild(&ctx.cache) {
+        Some(guild) => guild,
+        None => return false,
+    };
+
+    let role_name = env::var("DJ_ROLE").unwrap_or_else(|_| DEFAULT_DJ_ROLE.to_string());
+    let dj_role = guild
+        .roles
+        .values()
+        .find(|role| role.name.eq_ignore_ascii_case(&role_name))
+        .map(|role| role.id);
+
+    if dj_role.is_none() && open_without_role {
+        return true;
+    }
+
+    let member = match msg.member(ctx).await {
+        Ok(member) => member,
+        Err(_) => return false,
+    };
+
+    dj_role.map_or(false, |role| member.roles.contains(&role))
+        || member
+            .permissions(&ctx.cache)
+            .map_or(false, |permissions| permissions.administrator())
+}
+
+/// Builds an embed describing a track from its yt-dlp metadata: title, uploader, duration,
+/// thumbnail and, for tracks queued through `play`, who requested it.
+pub fn track_embed(heading: &str, handle: &TrackHandle, data: Option<&TrackData>) -> CreateEmbed {
+    let metadata = handle.metadata();
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(heading))
+        .title(metadata.title.as_deref().unwrap_or("Unknown title"));
+
+    if let Some(url) = data.and_then(TrackData::link) {
+        embed.url(url);
+    }
+
+    if let Some(uploader) = metadata.channel.as_ref().or(metadata.artist.as_ref()) {
+        embed.field("Uploader", uploader, true);
+    }
+
+    embed.field(
+        "Duration",
+        metadata
+            .duration
+            .map(format_duration)
+            .unwrap_or_else(|| "live".to_string()),
+        true,
+    );
+
+    if let Some(data) = data {
+        embed.field("Requested by", format!("<@{}>", data.requester), true);
+    }
+
+    if let Some(thumbnail) = &metadata.thumbnail {
+        embed.thumbnail(thumbnail);
+    }
+
+    embed
+}
+
+/// Orders items so their owners take turns: each owner's first item, in order of who appears
+/// first, then each owner's second, and so on. `current` is the owner of the item in use,
+/// whose turn has already started. Returns indices into `owners`.
+pub fn round_robin_order<T: PartialEq + Copy>(current: Option<T>, owners: &[T]) -> Vec<usize> {
+    let mut order: Vec<usize> = Vec::new();
+
+    for (index, owner) in owners.iter().enumerate() {
+        let placed: Vec<T> = order.iter().map(|&placed| owners[placed]).collect();
+        order.insert(fair_slot(current, &placed, *owner), index);
+    }
+
+    order
+}
+
+/// Where a new item of `owner` goes among `upcoming` so owners take turns, without moving
+/// anything already there. An owner's first item is in the first round, their second in the
+/// next and so on, with the item in use counting as the first of `current`'s. The new item
+/// goes in front of the first item from a later round, but never ahead of its owner's own.
+pub fn fair_slot<T: PartialEq + Copy>(current: Option<T>, upcoming: &[T], owner: T) -> usize {
+    let mut seen: Vec<T> = current.into_iter().collect();
+    let mut rounds = Vec::with_capacity(upcoming.len());
+
+    for item in upcoming {
+        rounds.push(seen.iter().filter(|seen| *seen == item).count());
+        seen.push(*item);
+    }
+
+    let round = seen.iter().filter(|seen| **seen == owner).count();
+    let after_own = upcoming
+        .iter()
+        .rposition(|item| *item == owner)
+        .map_or(0, |index| index + 1);
+    let before_later = rounds
+        .iter()
+        .position(|item_round| *item_round > round)
+        .unwrap_or(upcoming.len());
+
+    before_later.max(after_own)
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` when it is an hour or longer.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Draws a text progress bar such as `▬▬▬▬🔘▬▬▬▬▬`.
+pub fn progress_bar(position: Duration, duration: Duration) -> String {
+    const WIDTH: usize = 20;
+
+    let filled = if duration.is_zero() {
+        0
+    } else {
+        ((position.as_secs_f64() / duration.as_secs_f64()) * WIDTH as f64) as usize
+    }
+    .min(WIDTH - 1);
+
+    format!("{}🔘{}", "▬".repeat(filled), "▬".repeat(WIDTH - 1 - filled))
+}
+
+/// Parses a timestamp written as `ss`, `m:ss` or `h:mm:ss`.
+pub fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let mut secs = 0u64;
+
+    for (index, part) in timestamp.trim().split(':').enumerate() {
+        if index > 2 {
+            return None;
+        }
+
+        secs = secs
+            .checked_mul(60)?
+            .checked_add(part.parse::<u64>().ok()?)?;
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+/// Estimates how long until the track at `index` of the queue starts: what's left of the
+/// current track plus the length of everything between them. Passing the queue's length
+/// gives the time until it runs out. Returns `None` when one of those tracks has no known
+/// length, as with live streams.
+pub async fn time_until(
+    guild_id: GuildId,
+    tracks: &[TrackHandle],
+    index: usize,
+) -> Option<Duration> {
+    let current = match tracks.first() {
+        Some(current) if index > 0 => current,
+        _ => return Some(Duration::ZERO),
+    };
+
+    let speed = get_filter(guild_id).await.speed();
+    let position = current
+        .get_info()
+        .await
+        .map(|state| state.position)
+        .unwrap_or_default();
+    let lengths: Vec<Option<Duration>> = tracks
+        .iter()
+        .take(index)
+        .map(|track| track.metadata().duration)
+        .collect();
+
+    wait_time(&lengths, position, speed)
+}
+
+/// How long the tracks with `lengths` take to play through when the first has been playing
+/// for `position` and everything runs at `speed`.
+fn wait_time(lengths: &[Option<Duration>], position: Duration, speed: f32) -> Option<Duration> {
+    let (current, upcoming) = match lengths.split_first() {
+        Some(split) => split,
+        None => return Some(Duration::ZERO),
+    };
+
+    // Positions count time played, so lengths are scaled to match sped up playback.
+    let mut total = current?.div_f32(speed).saturating_sub(position);
+    for length in upcoming {
+        total += length?.div_f32(speed);
+    }
+
+    Some(total)
+}
+
+/// Formats a longer span of time compactly, e.g. `2h14m` or `7m05s`.
+pub fn format_length(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+    if hours > 0 {
+        format!("{}h{:02}m", hours, minutes)
+    } else {
+        format!("{}m{:02}s", minutes, seconds)
+    }
+}
+
+/// Writes the queue of every guild the bot is playing in to disk. Guilds without a call or
+/// with an empty queue are dropped from the saved state.
+pub async fn save_queues(manager: &Songbird, guild_ids: impl IntoIterator<Item = GuildId>) {
+    if !QUEUES_RESTORED.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let mut saved = HashMap::new();
+
+    for guild_id in guild_ids {
+        let handler_lock = match manager.get(guild_id) {
+            Some(handler_lock) => handler_lock,
+            None => continue,
+        };
+
+        let (voice_channel, tracks) = {
+            let handler = handler_lock.lock().await;
+            (handler.current_channel(), handler.queue().current_queue())
+        };
+
+        let voice_channel = match voice_channel {
+            Some(channel) if !tracks.is_empty() => ChannelId(channel.0),
+            _ => continue,
+        };
+
+        let elapsed = match tracks[0].get_info().await {
+            Ok(state) => state.position,
+            Err(_) => Duration::ZERO,
+        };
+
+        let mut track_list = Vec::with_capacity(tracks.len());
+        for track in &tracks {
+            if let Some(data) = track_data(track).await {
+                track_list.push(data);
+            }
+        }
+
+        saved.insert(
+            guild_id,
+            SavedQueue {
+                voice_channel,
+                tracks: track_list,
+                elapsed,
+            },
+        );
+    }
+
+    if let Err(why) = storage::save(QUEUES_FILE, &saved) {
+        println!("Error saving queues: {:?}", why);
+    }
+}
+
+/// Rejoins the voice channels saved by `save_queues` and re-enqueues their tracks, seeking
+/// the first one back to where it was. Only runs once per process.
+async fn restore_queues(ctx: &Context) -> bool {
+    if QUEUES_RESTORED.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    let saved: HashMap<GuildId, SavedQueue> = storage::load(QUEUES_FILE);
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    for (guild_id, queue) in saved {
+        let text_channel = match queue.tracks.first() {
+            Some(data) => data.channel,
+            None => continue,
+        };
+
+        let handler_lock = match join_voice(ctx, guild_id, queue.voice_channel, text_channel).await
+        {
+            Ok(handler_lock) => handler_lock,
+            Err(why) => {
+                println!("Failed to rejoin voice in {}: {:?}", guild_id, why);
+                continue;
+            }
+        };
+
+        let mut restored = 0;
+
+        for (index, data) in queue.tracks.into_iter().enumerate() {
+            let source = match source::open(guild_id, data.url.clone()).await {
+                Ok(source) => source,
+                Err(why) => {
+                    println!("Err restoring {}: {:?}", data.url, why);
+                    continue;
+                }
+            };
+
+            let mut handler = handler_lock.lock().await;
+            let song = enqueue(ctx, guild_id, &mut handler, source.into(), data).await;
+
+            if index == 0 && !queue.elapsed.is_zero() {
+                let _ = song.seek_time(queue.elapsed);
+            }
+
+            restored += 1;
+        }
+
+        println!("Restored {} tracks in guild {}", restored, guild_id);
+
+        let current = handler_lock.lock().await.queue().current();
+        if let Some(current) = current {
+            announce_now_playing(ctx, &current).await;
+        }
+    }
+
+    true
+}
+
+/// Restores saved queues, then keeps saving them so a crash only loses the
+/// last save interval.
+pub async fn persist_queues(ctx: Context) {
+    if !restore_queues(&ctx).await {
+        return;
+    }
+
+    let manager = songbird::get(&ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    let mut interval = tokio::time::interval(QUEUE_SAVE_INTERVAL);
+    loop {
+        interval.tick().await;
+        save_queues(&manager, ctx.cache.guilds()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration_pads_minutes_and_seconds() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1:05");
+        assert_eq!(format_duration(Duration::from_secs(3599)), "59:59");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1:00:00");
+        assert_eq!(format_duration(Duration::from_secs(37_230)), "10:20:30");
+    }
+
+    #[test]
+    fn progress_bar_places_marker_by_position() {
+        let length = Duration::from_secs(100);
+
+        assert_eq!(
+            progress_bar(Duration::ZERO, length),
+            format!("🔘{}", "▬".repeat(19))
+        );
+        assert_eq!(
+            progress_bar(Duration::from_secs(50), length),
+            format!("{}🔘{}", "▬".repeat(10), "▬".repeat(9))
+        );
+        assert_eq!(
+            progress_bar(length, length),
+            format!("{}🔘", "▬".repeat(19))
+        );
+    }
+
+    #[test]
+    fn progress_bar_handles_unknown_and_overrun_lengths() {
+        let empty = format!("🔘{}", "▬".repeat(19));
+        let full = format!("{}🔘", "▬".repeat(19));
+
+        assert_eq!(progress_bar(Duration::from_secs(5), Duration::ZERO), empty);
+        assert_eq!(
+            progress_bar(Duration::from_secs(500), Duration::from_secs(100)),
+            full
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_accepts_seconds_minutes_and_hours() {
+        assert_eq!(parse_timestamp("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_timestamp("1:30"), Some(Duration::from_secs(90)));
+        assert_eq!(
+            parse_timestamp(" 1:02:03 "),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(parse_timestamp("0:90"), Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_malformed_input() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("1:"), None);
+        assert_eq!(parse_timestamp("-5"), None);
+        assert_eq!(parse_timestamp("1:30s"), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_overflow() {
+        assert_eq!(parse_timestamp("18446744073709551616"), None);
+        assert_eq!(parse_timestamp("18446744073709551615:00"), None);
+        assert_eq!(
+            parse_timestamp("18446744073709551615"),
+            Some(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn format_length_shows_two_units() {
+        assert_eq!(format_length(Duration::from_secs(0)), "0m00s");
+        assert_eq!(format_length(Duration::from_secs(425)), "7m05s");
+        assert_eq!(format_length(Duration::from_secs(8040)), "2h14m");
+        assert_eq!(format_length(Duration::from_secs(8099)), "2h14m");
+    }
+
+    #[test]
+    fn round_robin_order_takes_turns_in_order_of_appearance() {
+        assert_eq!(
+            round_robin_order(None, &[1, 1, 2, 3, 2]),
+            vec![0, 2, 3, 1, 4]
+        );
+        assert_eq!(round_robin_order::<u8>(None, &[]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn round_robin_order_counts_the_current_owners_turn() {
+        assert_eq!(round_robin_order(Some(1), &[1, 2, 1, 2]), vec![1, 0, 3, 2]);
+    }
+
+    #[test]
+    fn fair_slot_goes_before_the_next_round() {
+        assert_eq!(fair_slot(None, &[1, 1, 1], 2), 1);
+        assert_eq!(fair_slot(None, &[1, 2, 1, 2], 3), 2);
+        assert_eq!(fair_slot(None, &[], 2), 0);
+    }
+
+    #[test]
+    fn fair_slot_counts_the_current_track() {
+        assert_eq!(fair_slot(Some(1), &[1, 1], 2), 0);
+        assert_eq!(fair_slot(Some(1), &[], 2), 0);
+    }
+
+    #[test]
+    fn fair_slot_never_goes_ahead_of_the_owners_own_tracks() {
+        assert_eq!(fair_slot(None, &[1, 1, 1, 2], 2), 4);
+    }
+
+    #[test]
+    fn wait_time_adds_what_is_left_of_the_current_track() {
+        let lengths = [
+            Some(Duration::from_secs(200)),
+            Some(Duration::from_secs(60)),
+        ];
+
+        assert_eq!(wait_time(&[], Duration::ZERO, 1.0), Some(Duration::ZERO));
+        assert_eq!(
+            wait_time(&lengths, Duration::from_secs(50), 1.0),
+            Some(Duration::from_secs(210))
+        );
+        assert_eq!(
+            wait_time(&lengths[..1], Duration::from_secs(300), 1.0),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn wait_time_scales_lengths_by_speed() {
+        let lengths = [
+            Some(Duration::from_secs(200)),
+            Some(Duration::from_secs(60)),
+        ];
+
+        assert_eq!(
+            wait_time(&lengths, Duration::from_secs(50), 2.0),
+            Some(Duration::from_secs(80))
+        );
+    }
+
+    #[test]
+    fn wait_time_is_unknown_when_a_length_is() {
+        let lengths = [Some(Duration::from_secs(200)), None];
+
+        assert_eq!(wait_time(&lengths, Duration::ZERO, 1.0), None);
+        assert_eq!(wait_time(&[None], Duration::ZERO, 1.0), None);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, UserId},
+    prelude::Mutex,
+};
+
+use crate::storage;
+
+const PLAYLISTS_FILE: &str = "playlists";
+
+lazy_static! {
+    static ref PLAYLISTS: Mutex<HashMap<UserId, BTreeMap<String, Playlist>>> =
+        Mutex::new(storage::load(PLAYLISTS_FILE));
+}
+
+/// A queue snapshot saved by a user under a name.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Playlist {
+    pub tracks: Vec<SavedTrack>,
+    /// The guilds whose other members can list and load it.
+    #[serde(default)]
+    pub shared_in: BTreeSet<GuildId>,
+}
+
+impl Playlist {
+    pub fn is_shared_in(&self, guild_id: GuildId) -> bool {
+        self.shared_in.contains(&guild_id)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SavedTrack {
+    pub url: String,
+    pub title: String,
+}
+
+/// The playlists a user has saved, by name.
+pub async fn user_playlists(owner: UserId) -> BTreeMap<String, Playlist> {
+    PLAYLISTS
+        .lock()
+        .await
+        .get(&owner)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Changes a user's playlists and saves them, returning whatever `update` does.
+pub async fn update_playlists<T>(
+    owner: UserId,
+    update: impl FnOnce(&mut BTreeMap<String, Playlist>) -> T,
+) -> T {
+    let mut playlists = PLAYLISTS.lock().await;
+    let result = update(playlists.entry(owner).or_default());
+
+    if let Err(why) = storage::save(PLAYLISTS_FILE, &*playlists) {
+        println!("Error saving playlists: {:?}", why);
+    }
+
+    result
+}
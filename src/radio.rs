@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use reqwest::Client as RequestClient;
+use serenity::{
+    client::Context,
+    model::{channel::Message, prelude::ChannelId},
+    prelude::TypeMapKey,
+};
+use songbird::tracks::{PlayMode, TrackHandle};
+
+/// How often a queued stream is checked to see whether it has started playing.
+const START_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The song an internet radio stream last reported, stored in its track's typemap.
+pub struct StreamTitle;
+
+impl TypeMapKey for StreamTitle {
+    type Value = String;
+}
+
+/// The song a radio track is currently playing, if its stream reports one.
+pub async fn stream_title(handle: &TrackHandle) -> Option<String> {
+    handle.typemap().read().await.get::<StreamTitle>().cloned()
+}
+
+/// Follows the ICY metadata of a radio stream while its track plays, posting a message with
+/// the current song and editing it whenever the song changes. Streams without ICY metadata,
+/// such as HLS, are left alone.
+pub async fn watch(ctx: Context, handle: TrackHandle, url: String, channel: ChannelId) {
+    // Queued streams are watched once they start, so the message stays current.
+    loop {
+        match handle.get_info().await {
+            Ok(state) if matches!(state.playing, PlayMode::Play) => break,
+            Ok(state) if matches!(state.playing, PlayMode::Stop | PlayMode::End) => return,
+            Ok(_) => tokio::time::sleep(START_POLL_INTERVAL).await,
+            Err(_) => return,
+        }
+    }
+
+    let mut response = match RequestClient::new()
+        .get(&url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(why) => {
+            println!("Err reading stream metadata from {}: {:?}", url, why);
+            return;
+        }
+    };
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let metaint = match header("icy-metaint").and_then(|metaint| metaint.parse::<usize>().ok()) {
+        Some(metaint) if metaint > 0 => metaint,
+        _ => return,
+    };
+    let station = header("icy-name").unwrap_or_else(|| "The stream".to_string());
+
+    let mut reader = IcyReader::new(metaint);
+    let mut message: Option<Message> = None;
+
+    while let Ok(Some(chunk)) = response.chunk().await {
+        for block in reader.feed(&chunk) {
+            // Blocks arrive every `metaint` bytes even when nothing changed, so this also
+            // stops the download soon after the track finishes or is skipped.
+            let done = handle.get_info().await.map_or(true, |state| {
+                matches!(state.playing, PlayMode::Stop | PlayMode::End)
+            });
+            if done {
+                return;
+            }
+
+            let title = match parse_stream_title(&block) {
+                Some(title) if !title.is_empty() => title,
+                _ => continue,
+            };
+
+            let previous = handle
+                .typemap()
+                .write()
+                .await
+                .insert::<StreamTitle>(title.clone());
+            if previous.as_deref() == Some(title.as_str()) {
+                continue;
+            }
+
+            let content = format!("📻 **{}** is playing: {}", station, title);
+            match &mut message {
+                Some(message) => {
+                    if let Err(why) = message.edit(&ctx, |m| m.content(&content)).await {
+                        println!("Err updating stream title: {:?}", why);
+                    }
+                }
+                None => match channel.say(&ctx.http, &content).await {
+                    Ok(sent) => message = Some(sent),
+                    Err(why) => println!("Error sending message: {:?}", why),
+                },
+            }
+        }
+    }
+}
+
+/// Splits an ICY stream into its metadata blocks. Every `metaint` bytes of audio are followed
+/// by a length byte and then that many 16-byte units of metadata.
+struct IcyReader {
+    metaint: usize,
+    audio_left: usize,
+    metadata_left: Option<usize>,
+    metadata: Vec<u8>,
+}
+
+impl IcyReader {
+    fn new(metaint: usize) -> Self {
+        IcyReader {
+            metaint,
+            audio_left: metaint,
+            metadata_left: None,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Consumes a chunk of the stream, returning any metadata blocks it completed. Blocks
+    /// are empty when the stream had nothing new to report.
+    fn feed(&mut self, mut chunk: &[u8]) -> Vec<String> {
+        let mut blocks = Vec::new();
+
+        while !chunk.is_empty() {
+            if self.audio_left > 0 {
+                let skip = self.audio_left.min(chunk.len());
+                self.audio_left -= skip;
+                chunk = &chunk[skip..];
+                continue;
+            }
+
+            match self.metadata_left {
+                None => {
+                    self.metadata_left = Some(chunk[0] as usize * 16);
+                    chunk = &chunk[1..];
+                }
+                Some(left) => {
+                    let take = left.min(chunk.len());
+                    self.metadata.extend_from_slice(&chunk[..take]);
+                    chunk = &chunk[take..];
+                    self.metadata_left = Some(left - take);
+                }
+            }
+
+            if self.metadata_left == Some(0) {
+                let block = String::from_utf8_lossy(&self.metadata)
+                    .trim_end_matches('\0')
+                    .to_string();
+                blocks.push(block);
+
+                self.metadata.clear();
+                self.metadata_left = None;
+                self.audio_left = self.metaint;
+            }
+        }
+
+        blocks
+    }
+}
+
+/// Pulls the song out of a metadata block such as `StreamTitle='Artist - Song';`.
+fn parse_stream_title(block: &str) -> Option<String> {
+    let start = block.find("StreamTitle='")? + "StreamTitle='".len();
+    let end = block[start..]
+        .find("';")
+        .map_or(block.len(), |end| start + end);
+
+    Some(block[start..end].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four bytes of audio, a block holding `StreamTitle='A';`, four more bytes of audio and
+    /// an empty block.
+    fn stream() -> Vec<u8> {
+        let mut stream = b"abcd".to_vec();
+        stream.push(1);
+        stream.extend_from_slice(b"StreamTitle='A';");
+        stream.extend_from_slice(b"efgh");
+        stream.push(0);
+        stream
+    }
+
+    #[test]
+    fn icy_reader_splits_out_metadata_blocks() {
+        let mut reader = IcyReader::new(4);
+
+        assert_eq!(reader.feed(&stream()), vec!["StreamTitle='A';", ""]);
+    }
+
+    #[test]
+    fn icy_reader_handles_blocks_split_across_chunks() {
+        let mut reader = IcyReader::new(4);
+        let blocks: Vec<String> = stream()
+            .chunks(3)
+            .flat_map(|chunk| reader.feed(chunk))
+            .collect();
+
+        assert_eq!(blocks, vec!["StreamTitle='A';", ""]);
+    }
+
+    #[test]
+    fn icy_reader_trims_padding() {
+        let mut reader = IcyReader::new(1);
+        let mut stream = vec![b'a', 1];
+        stream.extend_from_slice(b"StreamTitle='';\0");
+
+        assert_eq!(reader.feed(&stream), vec!["StreamTitle='';"]);
+    }
+
+    #[test]
+    fn parse_stream_title_reads_the_title() {
+        assert_eq!(
+            parse_stream_title("StreamTitle='Artist - Song';StreamUrl='';"),
+            Some("Artist - Song".to_string())
+        );
+        assert_eq!(
+            parse_stream_title("StreamTitle='It's here';"),
+            Some("It's here".to_string())
+        );
+        assert_eq!(
+            parse_stream_title("StreamTitle=' Cut off"),
+            Some("Cut off".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_stream_title_needs_a_title() {
+        assert_eq!(parse_stream_title(""), None);
+        assert_eq!(parse_stream_title("StreamUrl='http://example.com';"), None);
+        assert_eq!(parse_stream_title("StreamTitle='';"), Some(String::new()));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    io,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use serenity::{async_trait, model::prelude::GuildId};
+use songbird::input::{
+    children_to_reader,
+    error::{Error as InputError, Result as InputResult},
+    restartable::{Restart, Restartable},
+    Codec, Container, Input, Metadata,
+};
+
+use crate::cache;
+use crate::library;
+use crate::music::get_filter;
+use crate::ytdl;
+
+/// Prefix of track URLs that name a file in the music directory.
+pub const LOCAL_PREFIX: &str = "local:";
+/// Prefix of track URLs that are internet radio or other live streams.
+pub const RADIO_PREFIX: &str = "radio:";
+
+/// How long yt-dlp's stream URLs are trusted after being looked up.
+const INFO_REUSE_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// Output arguments matching what songbird's own ffmpeg sources produce.
+const FFMPEG_OUTPUT_ARGS: [&str; 9] = [
+    "-f",
+    "s16le",
+    "-ac",
+    "2",
+    "-ar",
+    "48000",
+    "-acodec",
+    "pcm_f32le",
+    "-",
+];
+
+/// Creates a lazy, restartable source for a track URL that plays through the guild's audio
+/// filter. `local:` and `radio:` URLs and links to audio files are played directly by ffmpeg,
+/// anything else goes through yt-dlp. Metadata is resolved here, so a track's title and length
+/// are known as soon as it's queued, but decoding doesn't start until the track is played.
+pub async fn open(guild_id: GuildId, url: String) -> InputResult<Restartable> {
+    let target = if let Some(location) = url.strip_prefix(LOCAL_PREFIX) {
+        let (file, path) = match (library::find(location).await, library::full_path(location)) {
+            (Some(file), Some(path)) => (file, path),
+            _ => {
+                return Err(InputError::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in the music directory", location),
+                )))
+            }
+        };
+
+        Target::File {
+            input: path.to_string_lossy().into_owned(),
+            metadata: file.metadata(),
+        }
+    } else if let Some(stream_url) = url.strip_prefix(RADIO_PREFIX) {
+        let mut metadata = library::probe(stream_url, stream_url).await.metadata();
+        // Live streams have no length, even when ffprobe guesses one from what it read.
+        metadata.duration = None;
+
+        Target::File {
+            input: stream_url.to_string(),
+            metadata,
+        }
+    } else if library::is_audio_file(&url) {
+        Target::File {
+            metadata: library::probe(&url, &url).await.metadata(),
+            input: url,
+        }
+    } else {
+        Target::Ytdl(url)
+    };
+
+    Restartable::new(
+        FilteredSource {
+            guild_id,
+            target,
+            resolved: None,
+            started: None,
+        },
+        true,
+    )
+    .await
+}
+
+/// What a source reads from.
+enum Target {
+    /// A page yt-dlp resolves to an audio stream each time the source starts, unless its
+    /// audio is in the cache.
+    Ytdl(String),
+    /// A file or URL ffmpeg can read directly, with metadata found ahead of time.
+    File { input: String, metadata: Metadata },
+}
+
+/// Streams a track through ffmpeg. The guild's filter is looked up every time the source is
+/// (re)started, so upcoming tracks pick up filter changes and seeking the current track
+/// applies a new filter from that point on.
+struct FilteredSource {
+    guild_id: GuildId,
+    target: Target,
+    /// yt-dlp's output from when the track was queued, and when it was fetched.
+    resolved: Option<(Instant, Value)>,
+    /// Where ffmpeg was last started from.
+    started: Option<Start>,
+}
+
+/// A point where the source was (re)started, for turning songbird's playback positions into
+/// positions in the source while a filter changes the speed.
+struct Start {
+    playback: Duration,
+    source: Duration,
+    /// The speed the source has played at since.
+    speed: f32,
+}
+
+impl FilteredSource {
+    /// The position in the source that playback position `time` corresponds to. Seeks are
+    /// measured from the last restart at the speed in use since, which is exact when seeking
+    /// forward and close enough when seeking back past a filter change.
+    fn source_time(&self, time: Duration, speed: f32) -> Duration {
+        match &self.started {
+            Some(start) if time >= start.playback => {
+                start.source + (time - start.playback).mul_f32(start.speed)
+            }
+            Some(start) => start
+                .source
+                .saturating_sub((start.playback - time).mul_f32(start.speed)),
+            None => time.mul_f32(speed),
+        }
+    }
+}
+
+#[async_trait]
+impl Restart for FilteredSource {
+    async fn call_restart(&mut self, time: Option<Duration>) -> InputResult<Input> {
+        let (input, metadata) = match &self.target {
+            Target::Ytdl(url) => {
+                let video_id = ytdl::video_id(url);
+
+                match video_id.as_deref().and_then(cache::lookup) {
+                    Some((path, metadata)) => (path.to_string_lossy().into_owned(), metadata),
+                    None => {
+                        // Tracks that start soon after being queued reuse the lookup made
+                        // then, while its stream URL is still valid.
+                        let info =
+                            match self.resolved.take().filter(|(resolved_at, _)| {
+                                resolved_at.elapsed() < INFO_REUSE_WINDOW
+                            }) {
+                                Some((_, info)) => info,
+                                None => ytdl::info(url).await.map_err(yt_dlp_error)?,
+                            };
+                        let stream_url = match info.get("url").and_then(|url| url.as_str()) {
+                            Some(stream_url) => stream_url.to_string(),
+                            None => return Err(InputError::YouTubeDlUrl(info)),
+                        };
+
+                        // Stream this time, and have it ready on disk for the next.
+                        if let Some(video_id) = video_id {
+                            tokio::spawn(cache::store(video_id, url.clone(), info.clone()));
+                        }
+
+                        (stream_url, Metadata::from_ytdl_output(info))
+                    }
+                }
+            }
+            Target::File { input, metadata } => (input.clone(), metadata.clone()),
+        };
+
+        let mut ffmpeg = Command::new("ffmpeg");
+
+        // Reconnecting is an HTTP option, which ffmpeg rejects for local files.
+        if input.starts_with("http") {
+            ffmpeg.args([
+                "-reconnect",
+                "1",
+                "-reconnect_streamed",
+                "1",
+                "-reconnect_delay_max",
+                "5",
+            ]);
+        }
+
+        // Songbird seeks by playback time, which runs faster or slower than the source's own
+        // while the speed is changed.
+        let filter = get_filter(self.guild_id).await;
+        let playback = time.unwrap_or_default();
+        let source_time = self.source_time(playback, filter.speed());
+
+        // Live streams can only be rejoined where they are now.
+        if !source_time.is_zero() && metadata.duration.is_some() {
+            ffmpeg.args(["-ss", &format!("{:.3}", source_time.as_secs_f64())]);
+        }
+
+        ffmpeg.arg("-i").arg(&input);
+
+        if let Some(filter) = filter.ffmpeg_args() {
+            ffmpeg.args(["-af", &filter]);
+        }
+
+        let child = ffmpeg
+            .args(FFMPEG_OUTPUT_ARGS)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        self.started = Some(Start {
+            playback,
+            source: source_time,
+            speed: filter.speed(),
+        });
+
+        Ok(Input::new(
+            true,
+            children_to_reader::<f32>(vec![child]),
+            Codec::FloatPcm,
+            Container::Raw,
+            Some(metadata),
+        ))
+    }
+
+    async fn lazy_init(&mut self) -> InputResult<(Option<Metadata>, Codec, Container)> {
+        let metadata = match &self.target {
+            Target::Ytdl(url) => match ytdl::video_id(url).as_deref().and_then(cache::lookup) {
+                Some((_, metadata)) => metadata,
+                None => {
+                    let info = ytdl::info(url).await.map_err(yt_dlp_error)?;
+                    self.resolved = Some((Instant::now(), info.clone()));
+                    Metadata::from_ytdl_output(info)
+                }
+            },
+            Target::File { metadata, .. } => metadata.clone(),
+        };
+
+        Ok((Some(metadata), Codec::FloatPcm, Container::Raw))
+    }
+}
+
+fn yt_dlp_error(why: Box<dyn std::error::Error + Send + Sync>) -> InputError {
+    InputError::Io(io::Error::new(io::ErrorKind::Other, why))
+}
@@ -0,0 +1,45 @@
+use std::{env, fs, io, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const DEFAULT_DATA_DIR: &str = "data";
+
+/// Directory that persisted state is kept in, overridable with `DATA_DIR`.
+pub fn data_dir() -> PathBuf {
+    env::var("DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_DATA_DIR))
+}
+
+fn path_for(name: &str) -> PathBuf {
+    data_dir().join(format!("{}.json", name))
+}
+
+/// Loads a JSON document from the data directory, falling back to the default value when it
+/// is missing or can't be read.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = path_for(name);
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|why| {
+            println!("Error parsing {}: {:?}", path.display(), why);
+            T::default()
+        }),
+        Err(why) if why.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(why) => {
+            println!("Error reading {}: {:?}", path.display(), why);
+            T::default()
+        }
+    }
+}
+
+/// Writes a JSON document to the data directory, replacing any previous copy in one step so
+/// a crash mid-write can't leave it truncated.
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let path = path_for(name);
+    fs::create_dir_all(data_dir())?;
+
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(temp_path, path)
+}
@@ -1,9 +1,71 @@
-use serenity::model::prelude::Message;
+use std::time::Duration;
+
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::prelude::{ChannelId, Message, UserId};
 use serenity::Result;
 
+const PREVIOUS_PAGE: char = '⬅';
+const NEXT_PAGE: char = '➡';
+const PAGE_TIMEOUT: Duration = Duration::from_secs(120);
+
 /// Checks that a message successfully sent; if not, then logs why to stdout.
 pub fn check_msg(result: Result<Message>) {
     if let Err(why) = result {
         println!("Error sending message: {:?}", why);
     }
 }
+
+/// Sends a list of embeds as a single message, letting `author` flip through them with
+/// reactions until the controls time out.
+pub async fn send_paginated(
+    ctx: &Context,
+    channel_id: ChannelId,
+    author: UserId,
+    pages: Vec<CreateEmbed>,
+) -> Result<()> {
+    let page_count = pages.len();
+    let mut page = 0;
+
+    let first_page = match pages.first() {
+        Some(embed) => embed.clone(),
+        None => return Ok(()),
+    };
+
+    let mut message = channel_id
+        .send_message(&ctx.http, |m| m.set_embed(first_page))
+        .await?;
+
+    if page_count < 2 {
+        return Ok(());
+    }
+
+    message.react(ctx, PREVIOUS_PAGE).await?;
+    message.react(ctx, NEXT_PAGE).await?;
+
+    while let Some(action) = message
+        .await_reaction(ctx)
+        .author_id(author)
+        .timeout(PAGE_TIMEOUT)
+        .await
+    {
+        let reaction = action.as_inner_ref();
+        let emoji = reaction.emoji.to_string();
+
+        page = match emoji.trim_end_matches('\u{fe0f}').chars().next() {
+            Some(PREVIOUS_PAGE) => (page + page_count - 1) % page_count,
+            Some(NEXT_PAGE) => (page + 1) % page_count,
+            _ => continue,
+        };
+
+        // Removing the user's reaction needs Manage Messages, so this may fail quietly.
+        let _ = reaction.delete(ctx).await;
+
+        let embed = pages[page].clone();
+        message.edit(ctx, |m| m.set_embed(embed)).await?;
+    }
+
+    let _ = message.delete_reactions(ctx).await;
+
+    Ok(())
+}
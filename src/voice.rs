@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex as StdMutex},
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use serenity::{
+    async_trait,
+    client::Context,
+    model::prelude::{ChannelId, GuildId, Message},
+    prelude::Mutex,
+};
+use songbird::{error::JoinResult, Call, Event, EventContext, EventHandler as VoiceEventHandler};
+
+use crate::utils::check_msg;
+
+const DEFAULT_IDLE_TIMEOUT: u64 = 300;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+lazy_static! {
+    /// The text channel each guild's voice session was started from, used for announcements.
+    static ref TEXT_CHANNELS: Mutex<HashMap<GuildId, ChannelId>> = Mutex::new(HashMap::new());
+}
+
+/// Joins a voice channel and remembers `text_channel` for announcements. The idle check is
+/// registered the first time a call is created for the guild.
+pub async fn join_voice(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    text_channel: ChannelId,
+) -> JoinResult<Arc<Mutex<Call>>> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    let new_call = manager.get(guild_id).is_none();
+    let (handler_lock, result) = manager.join(guild_id, channel_id).await;
+    result?;
+
+    TEXT_CHANNELS.lock().await.insert(guild_id, text_channel);
+
+    if new_call {
+        handler_lock.lock().await.add_global_event(
+            Event::Periodic(IDLE_CHECK_INTERVAL, None),
+            IdleChecker {
+                ctx: ctx.clone(),
+                guild_id,
+                idle_since: StdMutex::new(None),
+            },
+        );
+    }
+
+    Ok(handler_lock)
+}
+
+/// Finds the voice channel the author of `msg` is currently in.
+pub fn author_voice_channel(ctx: &Context, msg: &Message) -> Option<ChannelId> {
+    msg.guild(&ctx.cache)?
+        .voice_states
+        .get(&msg.author.id)
+        .and_then(|voice_state| voice_state.channel_id)
+}
+
+/// Gets the call a command should play into, joining the author's voice channel when the bot
+/// isn't connected yet or is sitting idle elsewhere. Replies with the reason and returns
+/// `None` when there's no channel to use, or when the bot is busy in another channel.
+pub async fn ensure_voice(ctx: &Context, msg: &Message) -> Option<Arc<Mutex<Call>>> {
+    let guild_id = msg.guild_id?;
+    let author_channel = author_voice_channel(ctx, msg);
+
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let (current_channel, busy) = {
+            let handler = handler_lock.lock().await;
+            let current_channel = handler
+                .current_channel()
+                .map(|channel| ChannelId(channel.0));
+            (current_channel, !handler.queue().is_empty())
+        };
+
+        match (current_channel, author_channel) {
+            (Some(current), Some(author)) if current != author && busy => {
+                check_msg(
+                    msg.reply(ctx, format!("I'm already playing in <#{}>", current))
+                        .await,
+                );
+
+                return None;
+            }
+            (Some(current), Some(author)) if current != author => {}
+            (Some(_), _) => return Some(handler_lock),
+            (None, _) => {}
+        }
+    }
+
+    let connect_to = match author_channel {
+        Some(channel) => channel,
+        None => {
+            check_msg(msg.reply(ctx, "Not in a voice channel").await);
+
+            return None;
+        }
+    };
+
+    match join_voice(ctx, guild_id, connect_to, msg.channel_id).await {
+        Ok(handler_lock) => Some(handler_lock),
+        Err(why) => {
+            check_msg(
+                msg.channel_id
+                    .say(&ctx.http, format!("Failed to join: {:?}", why))
+                    .await,
+            );
+
+            None
+        }
+    }
+}
+
+/// Disconnects from voice in a guild, dropping its queue, and says why in the text channel
+/// the session was started from.
+pub async fn leave_voice(ctx: &Context, guild_id: GuildId, reason: &str) {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    if let Err(why) = manager.remove(guild_id).await {
+        println!("Failed to leave voice in {}: {:?}", guild_id, why);
+        return;
+    }
+
+    if let Some(text_channel) = TEXT_CHANNELS.lock().await.remove(&guild_id) {
+        check_msg(text_channel.say(&ctx.http, reason).await);
+    }
+}
+
+/// Counts the members other than bots in a voice channel.
+pub fn humans_in_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> usize {
+    let guild = match ctx.cache.guild(guild_id) {
+        Some(guild) => guild,
+        None => return 0,
+    };
+
+    guild
+        .voice_states
+        .values()
+        .filter(|state| state.channel_id == Some(channel_id))
+        .filter(|state| {
+            let is_bot = match &state.member {
+                Some(member) => member.user.bot,
+                None => ctx.cache.user(state.user_id).map_or(false, |user| user.bot),
+            };
+
+            !is_bot
+        })
+        .count()
+}
+
+/// Leaves the guild's voice channel if someone just left `left_channel` and it was the last
+/// member other than bots in the bot's channel. Called after the cache has been updated.
+pub async fn leave_if_alone(ctx: &Context, guild_id: GuildId, left_channel: ChannelId) {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation")
+        .clone();
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return,
+    };
+
+    let channel_id = match handler_lock.lock().await.current_channel() {
+        Some(channel) => ChannelId(channel.0),
+        None => return,
+    };
+
+    if channel_id != left_channel {
+        return;
+    }
+
+    if humans_in_channel(ctx, guild_id, channel_id) == 0 {
+        leave_voice(
+            ctx,
+            guild_id,
+            "Everyone left, so I left the voice channel too",
+        )
+        .await;
+    }
+}
+
+fn idle_timeout() -> Duration {
+    let secs = env::var("IDLE_TIMEOUT")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(DEFAULT_IDLE_TIMEOUT);
+
+    Duration::from_secs(secs)
+}
+
+/// Periodically checks whether the queue has been empty for longer than the idle timeout,
+/// and leaves the channel if so.
+struct IdleChecker {
+    ctx: Context,
+    guild_id: GuildId,
+    idle_since: StdMutex<Option<Instant>>,
+}
+
+#[async_trait]
+impl VoiceEventHandler for IdleChecker {
+    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+        let manager = songbird::get(&self.ctx).await?;
+        let handler_lock = manager.get(self.guild_id)?;
+
+        let idle = handler_lock.lock().await.queue().is_empty();
+
+        let idle_for = {
+            let mut idle_since = self.idle_since.lock().unwrap();
+
+            if !idle {
+                *idle_since = None;
+                return None;
+            }
+
+            idle_since.get_or_insert_with(Instant::now).elapsed()
+        };
+
+        if idle_for >= idle_timeout() {
+            leave_voice(
+                &self.ctx,
+                self.guild_id,
+                "Left the voice channel after being idle",
+            )
+            .await;
+
+            return Some(Event::Cancel);
+        }
+
+        None
+    }
+}
@@ -0,0 +1,216 @@
+use std::{error::Error, time::Duration};
+
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::process::Command;
+
+use crate::cache;
+
+const YOUTUBE_DL_COMMAND: &str = "yt-dlp";
+/// Hosts that serve YouTube's watch pages.
+const YOUTUBE_HOSTS: [&str; 4] = [
+    "youtube.com",
+    "www.youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+];
+
+/// A single entry from yt-dlp's flat JSON output, as produced by searches and playlists.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VideoEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub duration: Option<f64>,
+    pub channel: Option<String>,
+    pub uploader: Option<String>,
+}
+
+impl VideoEntry {
+    /// The page URL of the video, falling back to a YouTube watch link built from the ID.
+    pub fn watch_url(&self) -> String {
+        match &self.url {
+            Some(url) if url.starts_with("http") => url.clone(),
+            _ => format!("https://www.youtube.com/watch?v={}", self.id),
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration.map(Duration::from_secs_f64)
+    }
+}
+
+/// Searches YouTube for `query` and returns up to `count` results, without resolving streams.
+pub async fn search(
+    query: &str,
+    count: usize,
+) -> Result<Vec<VideoEntry>, Box<dyn Error + Send + Sync>> {
+    flat_entries(&format!("ytsearch{}:{}", count, query)).await
+}
+
+/// Lists the entries of a playlist, without resolving streams for any of them.
+pub async fn playlist(url: &str) -> Result<Vec<VideoEntry>, Box<dyn Error + Send + Sync>> {
+    flat_entries(url).await
+}
+
+/// Fetches the full metadata of a single video, including the direct URL of its best audio
+/// stream.
+pub async fn info(url: &str) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let output = run(&[
+        "-j",
+        "-f",
+        "bestaudio/best",
+        "--no-playlist",
+        "--no-warnings",
+        url,
+    ])
+    .await?;
+
+    Ok(serde_json::from_slice(&output)?)
+}
+
+/// Downloads a video's best audio and converts it to `format`, saving it under the yt-dlp
+/// output template `output`.
+pub async fn download_audio(
+    url: &str,
+    output: &str,
+    format: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    run(&[
+        "-f",
+        "bestaudio/best",
+        "-x",
+        "--audio-format",
+        format,
+        "--no-playlist",
+        "--no-warnings",
+        "-o",
+        output,
+        url,
+    ])
+    .await?;
+
+    Ok(())
+}
+
+/// Lists videos YouTube considers related to a video, taken from the video's radio mix.
+pub async fn related(video_id: &str) -> Result<Vec<VideoEntry>, Box<dyn Error + Send + Sync>> {
+    flat_entries(&format!(
+        "https://www.youtube.com/watch?v={}&list=RD{}",
+        video_id, video_id
+    ))
+    .await
+}
+
+/// Extracts the video ID from a YouTube watch or short link. Links to other sites never have
+/// one, even if they look alike.
+pub fn video_id(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+
+    let id = match url.host_str()? {
+        "youtu.be" => url.path_segments()?.next()?.to_string(),
+        host if YOUTUBE_HOSTS.contains(&host) && url.path() == "/watch" => url
+            .query_pairs()
+            .find(|(key, _)| key == "v")
+            .map(|(_, id)| id.into_owned())?,
+        _ => return None,
+    };
+
+    Some(id).filter(|id| cache::is_valid_id(id))
+}
+
+/// Whether a URL points at a YouTube playlist rather than a single video. Links to a video
+/// that was opened from a playlist or mix also carry a `list=`, but they're for the video.
+pub fn is_playlist_url(url: &str) -> bool {
+    Url::parse(url).map_or(false, |url| {
+        url.host_str()
+            .map_or(false, |host| YOUTUBE_HOSTS.contains(&host))
+            && url.path() == "/playlist"
+    })
+}
+
+/// Runs yt-dlp in flat-playlist mode, parsing one JSON object per output line.
+async fn flat_entries(target: &str) -> Result<Vec<VideoEntry>, Box<dyn Error + Send + Sync>> {
+    let output = run(&["-j", "--flat-playlist", "--no-warnings", target]).await?;
+
+    let stdout = String::from_utf8_lossy(&output);
+    let entries = stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<VideoEntry>(line).ok())
+        .collect();
+
+    Ok(entries)
+}
+
+/// Runs yt-dlp with `args` and returns its standard output.
+async fn run(args: &[&str]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let output = Command::new(YOUTUBE_DL_COMMAND).args(args).output().await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            YOUTUBE_DL_COMMAND,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_id_reads_watch_and_short_links() {
+        let id = Some("dQw4w9WgXcQ".to_string());
+
+        assert_eq!(video_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), id);
+        assert_eq!(
+            video_id("https://youtube.com/watch?list=PL1&v=dQw4w9WgXcQ"),
+            id
+        );
+        assert_eq!(
+            video_id("https://music.youtube.com/watch?v=dQw4w9WgXcQ"),
+            id
+        );
+        assert_eq!(video_id("https://youtu.be/dQw4w9WgXcQ?t=42"), id);
+    }
+
+    #[test]
+    fn video_id_ignores_other_sites_and_pages() {
+        assert_eq!(video_id("https://notyoutube.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(
+            video_id("https://youtube.com.evil.example/watch?v=dQw4w9WgXcQ"),
+            None
+        );
+        assert_eq!(video_id("https://www.youtube.com/playlist?list=PL1"), None);
+        assert_eq!(video_id("https://www.youtube.com/watch"), None);
+        assert_eq!(video_id("https://www.youtube.com/watch?v=../config"), None);
+        assert_eq!(video_id("not a url"), None);
+    }
+
+    #[test]
+    fn is_playlist_url_only_matches_playlist_pages() {
+        assert!(is_playlist_url("https://www.youtube.com/playlist?list=PL1"));
+        assert!(!is_playlist_url(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1"
+        ));
+        assert!(!is_playlist_url(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=RDdQw4w9WgXcQ"
+        ));
+        assert!(!is_playlist_url("https://youtu.be/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn is_playlist_url_ignores_other_sites_and_local_files() {
+        assert!(!is_playlist_url("https://example.com/playlist?list=PL1"));
+        assert!(!is_playlist_url("https://www.youtube.com/playlists"));
+        assert!(!is_playlist_url("local:albums/playlist/song.mp3"));
+        assert!(!is_playlist_url("local:/playlist"));
+    }
+}